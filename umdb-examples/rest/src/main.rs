@@ -1,4 +1,4 @@
-use std::process::exit;
+use std::{env, path::PathBuf, process::exit};
 
 use actix_cors::Cors;
 use tokio::sync::mpsc;
@@ -13,10 +13,19 @@ async fn main() -> std::io::Result<()> {
     let umdb_instance = rest::create_umdb_handle(termination_signal_sender.downgrade());
     let port          = 8000;
 
-    umdb_instance.write().unwrap().umdb.configuration.adb_command = Some("adb".to_string());
+    {
+        let configuration = &mut umdb_instance.write().unwrap().umdb.configuration;
+
+        configuration.adb_command = Some("adb".to_string());
+
+        // Device PINs and patterns, as in `{ "R58M123": { "pin": "1234" } }`.
+        configuration.unlock_credentials_path = env::var_os("UMDB_UNLOCK_CREDENTIALS").map(PathBuf::from);
+    }
 
     env_logger::init();
 
+    let server_instance = umdb_instance.clone();
+
     println!("Starting server…");
 
     let server = HttpServer
@@ -26,7 +35,7 @@ async fn main() -> std::io::Result<()> {
         App::new().wrap(cors).service(
             web
             ::scope("/umdb")
-            .configure(|config| rest::actix_service::configure(config, umdb_instance.clone()))
+            .configure(|config| rest::actix_service::configure(config, server_instance.clone()))
         )
     })
     .bind(("127.0.0.1", port))
@@ -35,15 +44,20 @@ async fn main() -> std::io::Result<()> {
     println!("Listening for requests on port {port}");
 
     tokio::select!(
-        result = server.run() => { result.expect("An unknown error occurred while running the HTTP server") }
+        result = server.run() => {
+            result.expect("An unknown error occurred while running the HTTP server");
+
+            // The server stops on Ctrl-C and SIGTERM.
+            rest::actix_service::end_keep_awake_sessions(&umdb_instance).await;
+        }
 
         error = termination_signal_receiver.recv() => {
-            if let Some(error) = error {
-                if let Some(error) = error {
-                    eprintln!("A fatal error occurred: {:?}", error);
+            if let Some(Some(error)) = error {
+                eprintln!("A fatal error occurred: {:?}", error);
+
+                rest::actix_service::end_keep_awake_sessions(&umdb_instance).await;
 
-                    exit(1)
-                }
+                exit(1)
             }
         }
     );
@@ -0,0 +1,300 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::core::Configuration;
+
+use super::shell::{run_shell, ShellError};
+
+// Keeps the lines of `dumpsys window` this module reads: the full dump runs into the megabytes.
+const WINDOW_DUMP_COMMAND: &str = "dumpsys window | grep -E 'mCurrentFocus=|mFocusedApp=|mShowingLockscreen=|mIsShowing=' || true";
+
+const POWER_DUMP_COMMAND: &str = "dumpsys power | grep -E 'mWakefulness=' || true";
+
+static FOCUS_REGEXP: LazyLock<Regex>       = LazyLock::new(|| Regex::new(r"mCurrentFocus=Window\{\S+ \S+ ([^}]+)\}").unwrap());
+static KEYGUARD_REGEXP: LazyLock<Regex>    = LazyLock::new(|| Regex::new(r"(?:mKeyguardShowing|mShowingLockscreen|mIsShowing)=(true|false)").unwrap());
+static WAKEFULNESS_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"mWakefulness=(\w+)").unwrap());
+
+static RESUMED_ACTIVITY_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"ResumedActivity[:=] ?ActivityRecord\{\S+ \S+ ([^\s/]+)/([^\s}]+)").unwrap());
+static FOCUSED_APP_REGEXP: LazyLock<Regex>      = LazyLock::new(|| Regex::new(r"mFocusedApp=.*ActivityRecord\{\S+ \S+ ([^\s/]+)/([^\s}]+)").unwrap());
+static TASK_RECORD_REGEXP: LazyLock<Regex>      = LazyLock::new(|| Regex::new(r"(?m)^\s*(?:\* )?(?:Hist|Run) +#\d+: ActivityRecord\{(\S+) \S+ ([^\s/]+)/(\S+) t(\d+)").unwrap());
+
+#[derive(Serialize)]
+pub enum ActivityError {
+    Shell(ShellError),
+    NoForegroundActivity,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ForegroundActivity {
+    pub package: String,
+
+    // Fully qualified class name.
+    pub activity: String,
+
+    // As printed by Android, such as `com.example/.MainActivity`.
+    pub component: String,
+}
+
+impl ForegroundActivity {
+    // Activities can be given as a component, a fully qualified class name, or a class name relative to the package
+    // (`.MainActivity`) or to any of its namespaces (`MainActivity`).
+    pub fn matches(&self, activity: &str) -> bool {
+        let expanded_component = format!("{}/{}", self.package, self.activity);
+
+        activity == self.component
+        || activity == expanded_component
+        || activity == self.activity
+        || (activity.starts_with('.') && self.activity == format!("{}{activity}", self.package))
+        || self.activity.ends_with(&format!(".{}", activity.trim_start_matches('.')))
+    }
+}
+
+// Activities from the top of the task to its bottom.
+#[derive(Serialize)]
+pub struct Task {
+    pub id: u32,
+    pub activities: Vec<ForegroundActivity>,
+}
+
+// Tasks are listed from the top of the screen to the bottom, across stacks and displays. What cannot be found in the
+// output of the device, which varies a lot across Android versions, is `null`.
+#[derive(Serialize)]
+pub struct ActivityState {
+    pub resumed_activity: Option<ForegroundActivity>,
+
+    // Such as `com.example/com.example.MainActivity`, or `NotificationShade` when notifications are pulled down.
+    pub focused_window: Option<String>,
+
+    pub tasks: Vec<Task>,
+    pub screen_on: Option<bool>,
+    pub keyguard_showing: Option<bool>,
+}
+
+pub async fn adb_activity_state(configuration: &Configuration, device_id: &str) -> Result<ActivityState, ActivityError> {
+    let (activity_dump, window_dump, power_dump) = futures::join!(
+        run_shell(configuration, device_id, "dumpsys activity activities"),
+        run_shell(configuration, device_id, WINDOW_DUMP_COMMAND),
+        run_shell(configuration, device_id, POWER_DUMP_COMMAND),
+    );
+
+    Ok(parse_activity_state(
+        &activity_dump.map_err(ActivityError::Shell)?,
+        &window_dump.map_err(ActivityError::Shell)?,
+        &power_dump.map_err(ActivityError::Shell)?,
+    ))
+}
+
+// Cheaper than reading the whole state, for flows and waits that poll the foreground activity.
+pub(super) async fn read_foreground_activity(configuration: &Configuration, device_id: &str) -> Result<ForegroundActivity, ActivityError> {
+    let output = run_shell(configuration, device_id, "dumpsys activity activities | grep ResumedActivity || true")
+    .await
+    .map_err(ActivityError::Shell)?;
+
+    parse_resumed_activity(&output).ok_or(ActivityError::NoForegroundActivity)
+}
+
+// Recent versions report the keyguard in `dumpsys activity`, older ones only in `dumpsys window`.
+fn parse_activity_state(activity_dump: &str, window_dump: &str, power_dump: &str) -> ActivityState {
+    let keyguard_showing = KEYGUARD_REGEXP
+    .captures(activity_dump)
+    .or_else(|| KEYGUARD_REGEXP.captures(window_dump))
+    .map(|captures| &captures[1] == "true");
+
+    // Dreams, such as screen savers, are shown on a screen that is on.
+    let screen_on = WAKEFULNESS_REGEXP
+    .captures(power_dump)
+    .map(|captures| matches!(&captures[1], "Awake" | "Dreaming"));
+
+    ActivityState {
+        resumed_activity: parse_resumed_activity(activity_dump).or_else(|| parse_focused_app(window_dump)),
+        focused_window:   FOCUS_REGEXP.captures(window_dump).map(|captures| captures[1].to_string()),
+        tasks:            parse_tasks(activity_dump),
+        screen_on,
+        keyguard_showing,
+    }
+}
+
+// Lines look like `mResumedActivity: ActivityRecord{4f3c1a2 u0 com.example/.MainActivity t123}`, or
+// `topResumedActivity=ActivityRecord{...}` from Android 10.
+fn parse_resumed_activity(output: &str) -> Option<ForegroundActivity> {
+    let captures = RESUMED_ACTIVITY_REGEXP.captures(output)?;
+
+    Some(to_activity(&captures[1], &captures[2]))
+}
+
+// `mFocusedApp=ActivityRecord{...}`, or `mFocusedApp=AppWindowToken{... token=Token{... ActivityRecord{...}}}` before
+// Android 10.
+fn parse_focused_app(output: &str) -> Option<ForegroundActivity> {
+    let captures = FOCUSED_APP_REGEXP.captures(output)?;
+
+    Some(to_activity(&captures[1], &captures[2]))
+}
+
+// Tasks list their activities as `* Hist #1: ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}`, or as
+// `Run #1: ActivityRecord{...}` before Android 10, from the top of the task. Records can be listed more than once.
+fn parse_tasks(output: &str) -> Vec<Task> {
+    let mut tasks   = Vec::<Task>::new();
+    let mut records = HashSet::new();
+
+    for captures in TASK_RECORD_REGEXP.captures_iter(output) {
+        if !records.insert(captures[1].to_string()) {
+            continue;
+        }
+
+        let activity = to_activity(&captures[2], &captures[3]);
+
+        let Ok(id) = captures[4].parse() else {
+            continue;
+        };
+
+        match tasks.iter_mut().find(|task| task.id == id) {
+            Some(task) => task.activities.push(activity),
+            None       => tasks.push(Task { id, activities: vec![activity] }),
+        }
+    }
+
+    tasks
+}
+
+fn to_activity(package: &str, activity: &str) -> ForegroundActivity {
+    let package  = package.to_string();
+    let activity = activity.to_string();
+
+    ForegroundActivity {
+        component: format!("{package}/{activity}"),
+
+        activity: match activity.starts_with('.') {
+            true  => format!("{package}{activity}"),
+            false => activity,
+        },
+
+        package,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_activity_state, parse_resumed_activity};
+
+    // `dumpsys activity activities` on a Pixel 7 running Android 14, after opening the cart link. Trimmed.
+    const ACTIVITY_DUMP: &str = "\
+ACTIVITY MANAGER ACTIVITIES (dumpsys activity activities)
+Display #0 (activities from top to bottom):
+  * Task{8f1c5a0 #123 type=standard A=10123:com.example U=0 visible=true mode=fullscreen translucent=false sz=2}
+    mLastPausedActivity: ActivityRecord{1b2c3d4 u0 com.example/.MainActivity t123}
+    * Hist #1: ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}
+      Intent { act=android.intent.action.VIEW dat=example://cart flg=0x10000000 cmp=com.example/.CartActivity }
+    * Hist #0: ActivityRecord{1b2c3d4 u0 com.example/.MainActivity t123}
+  * Task{5d6e7f8 #1 type=home ?? U=0 visible=false}
+    * Hist #0: ActivityRecord{9a8b7c6 u0 com.google.android.apps.nexuslauncher/.NexusLauncherActivity t1}
+
+  Resumed activities in task display areas (from top to bottom):
+    Resumed: ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}
+  ResumedActivity: ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}
+  topResumedActivity=ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}
+
+KeyguardController:
+  mKeyguardShowing=false
+  mAodShowing=false
+";
+
+    // The filtered `dumpsys window` and `dumpsys power` of the same device.
+    const WINDOW_DUMP: &str = "\
+  mCurrentFocus=Window{7e6d5c4 u0 com.example/com.example.CartActivity}
+  mFocusedApp=ActivityRecord{4f3c1a2 u0 com.example/.CartActivity t123}
+";
+
+    const POWER_DUMP: &str = "  mWakefulness=Awake\n";
+
+    // The same dumps on a Nexus 5X running Android 8.1, locked with its screen off. The activity dump has no resumed
+    // activity while the device sleeps.
+    const LEGACY_ACTIVITY_DUMP: &str = "\
+ACTIVITY MANAGER ACTIVITIES (dumpsys activity activities)
+Display #0 (activities from top to bottom):
+  Stack #1: type=standard mode=fullscreen
+    Task id #42
+      * TaskRecord{5a4b3c2 #42 A=com.example U=0 StackId=1 sz=2}
+        Activities=[ActivityRecord{1112131 u0 com.example/.MainActivity t42}, ActivityRecord{2223242 u0 com.example/.CartActivity t42}]
+    Running activities (most recent first):
+      TaskRecord{5a4b3c2 #42 A=com.example U=0 StackId=1 sz=2}
+        Run #1: ActivityRecord{2223242 u0 com.example/.CartActivity t42}
+        Run #0: ActivityRecord{1112131 u0 com.example/.MainActivity t42}
+    mLastPausedActivity: ActivityRecord{2223242 u0 com.example/.CartActivity t42}
+";
+
+    const LEGACY_WINDOW_DUMP: &str = "\
+  mCurrentFocus=Window{a1b2c3d u0 StatusBar}
+  mFocusedApp=AppWindowToken{e4f5a6b token=Token{c7d8e9f ActivityRecord{2223242 u0 com.example/.CartActivity t42}}}
+    mShowingLockscreen=true mShowingDream=false mDreamingLockscreen=true
+";
+
+    const LEGACY_POWER_DUMP: &str = "  mWakefulness=Asleep\n";
+
+    #[test]
+    fn parses_activity_state() {
+        let state = parse_activity_state(ACTIVITY_DUMP, WINDOW_DUMP, POWER_DUMP);
+
+        let resumed_activity = state.resumed_activity.unwrap();
+
+        assert_eq!(resumed_activity.package, "com.example");
+        assert_eq!(resumed_activity.activity, "com.example.CartActivity");
+        assert_eq!(resumed_activity.component, "com.example/.CartActivity");
+
+        assert_eq!(state.focused_window.as_deref(), Some("com.example/com.example.CartActivity"));
+        assert_eq!(state.screen_on, Some(true));
+        assert_eq!(state.keyguard_showing, Some(false));
+
+        assert_eq!(state.tasks.len(), 2);
+        assert_eq!(state.tasks[0].id, 123);
+
+        let task_activities = state.tasks[0].activities.iter().map(|activity| activity.component.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(task_activities, ["com.example/.CartActivity", "com.example/.MainActivity"]);
+        assert_eq!(state.tasks[1].activities[0].activity, "com.google.android.apps.nexuslauncher.NexusLauncherActivity");
+    }
+
+    #[test]
+    fn parses_legacy_activity_state() {
+        let state = parse_activity_state(LEGACY_ACTIVITY_DUMP, LEGACY_WINDOW_DUMP, LEGACY_POWER_DUMP);
+
+        // Read from the focused app of the window manager.
+        assert_eq!(state.resumed_activity.unwrap().component, "com.example/.CartActivity");
+
+        assert_eq!(state.focused_window.as_deref(), Some("StatusBar"));
+        assert_eq!(state.screen_on, Some(false));
+        assert_eq!(state.keyguard_showing, Some(true));
+
+        // Activities are only listed once, although the task record lists them too.
+        assert_eq!(state.tasks.len(), 1);
+        assert_eq!(state.tasks[0].activities.len(), 2);
+    }
+
+    #[test]
+    fn reports_missing_state() {
+        let state = parse_activity_state("", "", "");
+
+        assert!(state.resumed_activity.is_none());
+        assert!(state.focused_window.is_none());
+        assert!(state.tasks.is_empty());
+        assert_eq!(state.screen_on, None);
+        assert_eq!(state.keyguard_showing, None);
+    }
+
+    #[test]
+    fn matches_activities() {
+        let activity = parse_resumed_activity("  mResumedActivity: ActivityRecord{4f3c1a2 u0 com.example/.checkout.CartActivity t123}").unwrap();
+
+        assert!(activity.matches("com.example/.checkout.CartActivity"));
+        assert!(activity.matches("com.example/com.example.checkout.CartActivity"));
+        assert!(activity.matches("com.example.checkout.CartActivity"));
+        assert!(activity.matches(".checkout.CartActivity"));
+        assert!(activity.matches("CartActivity"));
+        assert!(activity.matches(".CartActivity"));
+
+        assert!(!activity.matches("Activity"));
+        assert!(!activity.matches("com.example/.CartActivity"));
+        assert!(!activity.matches("MainActivity"));
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::BTreeMap, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use serde::Serialize;
+
+use crate::{common::baseline::{Baseline, BaselineDifference, BaselineRestoration, UnrestoredDifference}, core::Configuration};
+
+use super::{settings::{adb_get_setting, adb_set_setting, parse_display_size, NightMode, SettingName, SettingValue, SettingsError}, shell::{quote, run_shell, ShellError}};
+
+// Settings tests commonly depend on. Most others are device specific, or change on their own.
+const BASELINE_SETTINGS: [(&str, &[&str]); 3] = [
+    ("global", &[
+        "airplane_mode_on",
+        "always_finish_activities",
+        "animator_duration_scale",
+        "auto_time",
+        "auto_time_zone",
+        "development_settings_enabled",
+        "http_proxy",
+        "mobile_data",
+        "stay_on_while_plugged_in",
+        "transition_animation_scale",
+        "wifi_on",
+        "window_animation_scale",
+    ]),
+
+    ("secure", &[
+        "accessibility_display_magnification_enabled",
+        "accessibility_enabled",
+        "default_input_method",
+        "enabled_accessibility_services",
+        "enabled_input_methods",
+        "location_mode",
+        "long_press_timeout",
+        "show_ime_with_hard_keyboard",
+        "ui_night_mode",
+    ]),
+
+    // The locale is captured on its own, as it has fallbacks.
+    ("system", &[
+        "accelerometer_rotation",
+        "font_scale",
+        "haptic_feedback_enabled",
+        "pointer_location",
+        "screen_brightness",
+        "screen_brightness_mode",
+        "screen_off_timeout",
+        "show_touches",
+        "sound_effects_enabled",
+        "time_12_24",
+        "user_rotation",
+    ]),
+];
+
+#[derive(Serialize)]
+pub enum BaselineError {
+    Shell(ShellError),
+    Settings(SettingsError),
+    UnrecognizedOutput(String),
+}
+
+pub async fn adb_capture_baseline(configuration: &Configuration, device_id: &str) -> Result<Baseline, BaselineError> {
+    let commands = BASELINE_SETTINGS.map(|(namespace, _)| format!("settings list {namespace}"));
+
+    let namespaces = futures
+    ::future
+    ::join_all(commands.iter().map(|command| run_shell(configuration, device_id, command)))
+    .await;
+
+    let mut settings = BTreeMap::new();
+
+    for ((namespace, keys), output) in BASELINE_SETTINGS.into_iter().zip(namespaces) {
+        let output = output.map_err(BaselineError::Shell)?;
+
+        // Prints one `key=value` line per setting. Values may contain `=`.
+        let values = output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect::<BTreeMap<_, _>>();
+
+        let namespace_settings = keys
+        .iter()
+        .map(|key| {
+            let value = values
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| *value != "null")
+            .map(str::to_string);
+
+            (key.to_string(), value)
+        })
+        .collect();
+
+        settings.insert(namespace.to_string(), namespace_settings);
+    }
+
+    let display_size = match get_setting(configuration, device_id, SettingName::DisplaySize).await? {
+        SettingValue::DisplaySize(size) => size.map(|size| format!("{}x{}", size.width, size.height)),
+        _                               => None,
+    };
+
+    let display_density = match get_setting(configuration, device_id, SettingName::DisplayDensity).await? {
+        SettingValue::DisplayDensity(density) => density,
+        _                                     => None,
+    };
+
+    let locale = match get_setting(configuration, device_id, SettingName::Locale).await? {
+        SettingValue::Locale(locale) => locale,
+        _                            => String::new(),
+    };
+
+    Ok(Baseline {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_millis() as u64,
+        device_id: device_id.to_string(),
+        packages:  adb_user_package_versions(configuration, device_id).await?,
+        settings,
+        display_size,
+        display_density,
+        locale,
+    })
+}
+
+pub async fn adb_diff_baseline(configuration: &Configuration, device_id: &str, baseline: &Baseline) -> Result<Vec<BaselineDifference>, BaselineError> {
+    let current = adb_capture_baseline(configuration, device_id).await?;
+
+    Ok(baseline.diff(&current))
+}
+
+// Differences are restored one by one, so that a setting the device refuses does not prevent restoring the others. Apps
+// are never installed nor uninstalled: differing apps are only reported.
+pub async fn adb_restore_baseline(configuration: &Configuration, device_id: &str, baseline: &Baseline) -> Result<BaselineRestoration, BaselineError> {
+    let mut restoration = BaselineRestoration::default();
+
+    for difference in adb_diff_baseline(configuration, device_id, baseline).await? {
+        match restore_difference(configuration, device_id, &difference).await {
+            Ok(())      => restoration.restored.push(difference),
+            Err(reason) => restoration.unrestored.push(UnrestoredDifference { difference, reason }),
+        }
+    }
+
+    Ok(restoration)
+}
+
+async fn restore_difference(configuration: &Configuration, device_id: &str, difference: &BaselineDifference) -> Result<(), String> {
+    let value = match difference {
+        BaselineDifference::Setting { namespace, key, expected, .. } => {
+            return restore_setting(configuration, device_id, namespace, key, expected.as_deref()).await;
+        },
+
+        BaselineDifference::DisplaySize { expected: Some(size), .. } => {
+            SettingValue::DisplaySize(Some(parse_display_size(size).ok_or_else(|| format!("Invalid display size: {size}"))?))
+        },
+
+        BaselineDifference::DisplaySize { expected: None, .. } => SettingValue::DisplaySize(None),
+        BaselineDifference::DisplayDensity { expected, .. }    => SettingValue::DisplayDensity(*expected),
+        BaselineDifference::Locale { expected, .. }            => SettingValue::Locale(expected.clone()),
+        BaselineDifference::Package { expected: Some(_), .. }  => return Err("Apps cannot be installed by umdb".to_string()),
+        BaselineDifference::Package { expected: None, .. }     => return Err("Apps installed since the capture are not uninstalled".to_string()),
+    };
+
+    adb_set_setting(configuration, device_id, &value).await.map_err(describe_error)?;
+
+    Ok(())
+}
+
+async fn restore_setting(configuration: &Configuration, device_id: &str, namespace: &str, key: &str, value: Option<&str>) -> Result<(), String> {
+    let enabled = value.is_some_and(|value| value != "0");
+
+    // Radios are not turned on or off by writing their setting, and the night mode is not read again by the uimode
+    // service once written.
+    let typed_value = match (namespace, key) {
+        ("global", "airplane_mode_on") => Some(SettingValue::AirplaneMode(enabled)),
+        ("global", "wifi_on")          => Some(SettingValue::Wifi(enabled)),
+        ("global", "mobile_data")      => Some(SettingValue::MobileData(enabled)),
+        ("secure", "ui_night_mode")    => Some(SettingValue::DarkMode(parse_night_mode(value)?)),
+        _                              => None,
+    };
+
+    if let Some(typed_value) = typed_value {
+        return adb_set_setting(configuration, device_id, &typed_value).await.map(|_| ()).map_err(describe_error);
+    }
+
+    let command = match value {
+        Some(value) => format!("settings put {namespace} {key} {}", quote(value)),
+        None        => format!("settings delete {namespace} {key}"),
+    };
+
+    run_shell(configuration, device_id, &command).await.map_err(describe_error)?;
+
+    Ok(())
+}
+
+// The modes of `UiModeManager`. Devices that were never switched use the light theme.
+fn parse_night_mode(value: Option<&str>) -> Result<NightMode, String> {
+    match value {
+        Some("0")        => Ok(NightMode::Auto),
+        None | Some("1") => Ok(NightMode::No),
+        Some("2")        => Ok(NightMode::Yes),
+        Some("3")        => Ok(NightMode::Custom),
+        Some(other)      => Err(format!("Unknown night mode: {other}")),
+    }
+}
+
+async fn adb_user_package_versions(configuration: &Configuration, device_id: &str) -> Result<BTreeMap<String, u64>, BaselineError> {
+    let output = run_shell(configuration, device_id, "pm list packages -3 --show-versioncode")
+    .await
+    .map_err(BaselineError::Shell)?;
+
+    parse_package_versions(&output)
+}
+
+// Prints `package:com.example versionCode:42` lines.
+fn parse_package_versions(output: &str) -> Result<BTreeMap<String, u64>, BaselineError> {
+    output
+    .lines()
+    .filter_map(|line| line.trim().strip_prefix("package:"))
+    .map(|line| {
+        let (package, version_code) = line
+        .split_once(" versionCode:")
+        .ok_or_else(|| BaselineError::UnrecognizedOutput(line.to_string()))?;
+
+        let version_code = version_code
+        .parse()
+        .map_err(|_| BaselineError::UnrecognizedOutput(line.to_string()))?;
+
+        Ok((package.to_string(), version_code))
+    })
+    .collect()
+}
+
+async fn get_setting(configuration: &Configuration, device_id: &str, name: SettingName) -> Result<SettingValue, BaselineError> {
+    adb_get_setting(configuration, device_id, name).await.map_err(BaselineError::Settings)
+}
+
+// Errors are reported as the JSON the REST API would return for them.
+fn describe_error<T: Serialize>(error: T) -> String {
+    serde_json::to_string(&error).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_night_mode, parse_package_versions, NightMode};
+
+    // `pm list packages -3 --show-versioncode` on a Pixel 7 running Android 14.
+    const PACKAGE_VERSIONS_OUTPUT: &str = "\
+package:com.example versionCode:4200
+package:com.example.debug versionCode:4201
+package:com.android.chrome versionCode:636713333
+";
+
+    #[test]
+    fn parses_package_versions() {
+        let packages = parse_package_versions(PACKAGE_VERSIONS_OUTPUT).ok().unwrap();
+
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages["com.example"], 4200);
+        assert_eq!(packages["com.android.chrome"], 636713333);
+
+        assert!(parse_package_versions("").ok().unwrap().is_empty());
+        assert!(parse_package_versions("package:com.example\n").is_err());
+        assert!(parse_package_versions("package:com.example versionCode:latest\n").is_err());
+    }
+
+    #[test]
+    fn parses_night_modes() {
+        assert!(matches!(parse_night_mode(None), Ok(NightMode::No)));
+        assert!(matches!(parse_night_mode(Some("0")), Ok(NightMode::Auto)));
+        assert!(matches!(parse_night_mode(Some("2")), Ok(NightMode::Yes)));
+        assert!(parse_night_mode(Some("dark")).is_err());
+    }
+}
@@ -1,60 +1,181 @@
-use std::{net::IpAddr, time::Duration, process::ExitStatus};
+use std::{process::Output, time::{Duration, Instant}};
 
 use serde::Serialize;
-use tokio::{process::Command, time::timeout};
 
 use crate::core::Configuration;
 
+use super::{process::run_adb, target::device_arguments};
+
+const STATE_POLLING_INTERVAL: Duration = Duration::from_millis(250);
+
 #[derive(Serialize)]
 pub enum AdbConnectError {
+    CannotConnectToDevice(String),
+    CannotDisconnect(String),
     CannotRunProcess(String),
+    CannotSwitchAdbMode(String),
     DebugBridgePathMissing,
-    CannotConnectToDevice,
-    CannotSwitchAdbMode,
+    DeviceUnauthorized,
     DeviceUnresponsive,
 }
 
-pub async fn adb_connect(configuration: &Configuration, device_id: &str, ip: &IpAddr, port: u16) -> Result<(), AdbConnectError> {
-    let adb_command = configuration
-    .adb_command
-    .as_deref()
-    .ok_or(AdbConnectError::DebugBridgePathMissing)?;
+#[derive(Serialize)]
+pub enum ConnectionStatus {
+    Connected,
+    AlreadyConnected,
+}
 
-    let process_task = Command
-    ::new(adb_command)
-    .args(&["-s", device_id, "tcpip", &format!("{port}")])
-    .output();
+#[derive(Serialize)]
+pub struct Connection {
+    pub id:     String,
+    pub status: ConnectionStatus,
+}
 
-    let result = timeout(Duration::from_secs(1), process_task).await;
+// Legacy flow: restarts adbd on a USB device so that it listens on the given port, then connects to it.
+pub async fn adb_connect_from_usb(configuration: &Configuration, device_id: &str, host: &str, port: u16) -> Result<Connection, AdbConnectError> {
+    let adb_command = read_adb_command(configuration)?;
 
-    let output = match result {
-        Ok(Ok(output)) => output,
-        Err(_)         => return Err(AdbConnectError::DeviceUnresponsive),
-        Ok(Err(error)) => return Err(AdbConnectError::CannotRunProcess(error.to_string())),
-    };
+    let output = run_adb(adb_command, &device_arguments(device_id, &["tcpip", &port.to_string()]), &configuration.timeouts.connection).await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
 
-    if !ExitStatus::success(&output.status) {
-        return Err(AdbConnectError::CannotSwitchAdbMode)
+    if !output.status.success() || !stdout.contains("restarting in TCP mode") {
+        return Err(AdbConnectError::CannotSwitchAdbMode(combined_output(&output)));
     }
 
-    tokio::time::sleep(Duration::from_secs(1)).await;
+    // adbd takes a moment to restart: connection attempts are retried until it listens.
+    let started_at = Instant::now();
 
-    let process_task = Command
-    ::new(adb_command)
-    .args(&["connect", &format!("{ip}:{port}")])
-    .output();
+    loop {
+        match adb_connect(configuration, host, port).await {
+            Err(AdbConnectError::CannotConnectToDevice(_)) if started_at.elapsed() < configuration.timeouts.device_ready.timeout() => {
+                tokio::time::sleep(STATE_POLLING_INTERVAL).await;
+            },
+
+            result => return result,
+        }
+    }
+}
 
-    let result = timeout(Duration::from_secs(1), process_task).await;
+// Connects to a device that already listens for connections, then waits until it is usable.
+pub async fn adb_connect(configuration: &Configuration, host: &str, port: u16) -> Result<Connection, AdbConnectError> {
+    let adb_command = read_adb_command(configuration)?;
 
-    match result {
-        Ok(Ok(_))      => {},
-        Err(_)         => return Err(AdbConnectError::DeviceUnresponsive),
-        Ok(Err(error)) => return Err(AdbConnectError::CannotRunProcess(error.to_string())),
+    let id = match host.contains(':') {
+        true  => format!("[{host}]:{port}"),
+        false => format!("{host}:{port}"),
     };
 
-    if !ExitStatus::success(&output.status) {
-        return Err(AdbConnectError::CannotConnectToDevice)
+    // adb exits with a zero status when the connection fails, so its output has to be parsed.
+    let output = run_adb(adb_command, &["connect", &id], &configuration.timeouts.connection).await?;
+
+    let status = parse_connection_status(&String::from_utf8_lossy(&output.stdout))
+    .ok_or_else(|| AdbConnectError::CannotConnectToDevice(combined_output(&output)))?;
+
+    wait_until_ready(configuration, adb_command, &id).await?;
+
+    Ok(Connection { id, status })
+}
+
+pub async fn adb_disconnect(configuration: &Configuration, device_id: &str) -> Result<(), AdbConnectError> {
+    let adb_command = read_adb_command(configuration)?;
+
+    let output = run_adb(adb_command, &["disconnect", device_id], &configuration.timeouts.connection).await?;
+
+    match output.status.success() && String::from_utf8_lossy(&output.stdout).contains("disconnected") {
+        true  => Ok(()),
+        false => Err(AdbConnectError::CannotDisconnect(combined_output(&output))),
     }
+}
+
+// Restarts adbd on a device so that it only listens on USB again.
+pub async fn adb_back_to_usb(configuration: &Configuration, device_id: &str) -> Result<(), AdbConnectError> {
+    let adb_command = read_adb_command(configuration)?;
+
+    let output = run_adb(adb_command, &device_arguments(device_id, &["usb"]), &configuration.timeouts.connection).await?;
+
+    match output.status.success() && String::from_utf8_lossy(&output.stdout).contains("restarting in USB mode") {
+        true  => Ok(()),
+        false => Err(AdbConnectError::CannotSwitchAdbMode(combined_output(&output))),
+    }
+}
 
-    return Ok(());
+async fn wait_until_ready(configuration: &Configuration, adb_command: &str, id: &str) -> Result<(), AdbConnectError> {
+    let started_at = Instant::now();
+
+    loop {
+        let output = run_adb(adb_command, &["-s", id, "get-state"], &configuration.timeouts.connection).await?;
+
+        if String::from_utf8_lossy(&output.stdout).trim() == "device" {
+            return Ok(());
+        }
+
+        // Devices waiting for the user to accept the debugging key stay unauthorized until then.
+        if started_at.elapsed() >= configuration.timeouts.device_ready.timeout() {
+            return match String::from_utf8_lossy(&output.stderr).contains("unauthorized") {
+                true  => Err(AdbConnectError::DeviceUnauthorized),
+                false => Err(AdbConnectError::DeviceUnresponsive),
+            };
+        }
+
+        tokio::time::sleep(STATE_POLLING_INTERVAL).await;
+    }
+}
+
+// Failures are also reported as `... connect to <id>`.
+fn parse_connection_status(stdout: &str) -> Option<ConnectionStatus> {
+    if stdout.contains("already connected to") {
+        Some(ConnectionStatus::AlreadyConnected)
+    } else if stdout.contains("connected to") && !stdout.contains("failed to") && !stdout.contains("cannot") {
+        Some(ConnectionStatus::Connected)
+    } else {
+        None
+    }
+}
+
+fn read_adb_command(configuration: &Configuration) -> Result<&str, AdbConnectError> {
+    configuration
+    .adb_command
+    .as_deref()
+    .ok_or(AdbConnectError::DebugBridgePathMissing)
+}
+
+fn combined_output(output: &Output) -> String {
+    format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+    .trim()
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::{ExitStatus, Output};
+
+    use super::{combined_output, parse_connection_status, ConnectionStatus};
+
+    #[test]
+    fn recognizes_connection_outputs() {
+        // Printed by adb 35.0.2.
+        assert!(matches!(parse_connection_status("connected to 192.168.1.20:5555\n"), Some(ConnectionStatus::Connected)));
+        assert!(matches!(parse_connection_status("already connected to 192.168.1.20:5555\n"), Some(ConnectionStatus::AlreadyConnected)));
+        assert!(parse_connection_status("failed to connect to '192.168.1.20:5555': Connection refused\n").is_none());
+
+        // Printed by older adb versions.
+        assert!(parse_connection_status("cannot connect to 192.168.1.20:5555: No route to host (113)\n").is_none());
+        assert!(parse_connection_status("").is_none());
+    }
+
+    #[test]
+    fn combines_both_outputs() {
+        let output = Output {
+            status: ExitStatus::default(),
+            stdout: b"failed to connect to '192.168.1.20:5555': Connection refused\n".to_vec(),
+            stderr: b"* daemon not running; starting now at tcp:5037\n* daemon started successfully\n".to_vec(),
+        };
+
+        assert_eq!(combined_output(&output), "failed to connect to '192.168.1.20:5555': Connection refused\n* daemon not running; starting now at tcp:5037\n* daemon started successfully");
+
+        let output = Output { status: ExitStatus::default(), stdout: vec![], stderr: b"error: no devices/emulators found\n".to_vec() };
+
+        assert_eq!(combined_output(&output), "error: no devices/emulators found");
+    }
 }
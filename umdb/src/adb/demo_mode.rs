@@ -0,0 +1,170 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::Configuration;
+
+use super::shell::{quote, run_shell, ShellError};
+
+const DEFAULT_CLOCK: &str = "1200";
+
+const DEFAULT_BATTERY_LEVEL: u8 = 100;
+
+const DEFAULT_SIGNAL_LEVEL: u8 = 4;
+
+static CLOCK_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([01][0-9]|2[0-3])[0-5][0-9]$").unwrap());
+
+#[derive(Serialize)]
+pub enum DemoModeError {
+    Shell(ShellError),
+    InvalidOption(String),
+}
+
+// What the status bar shows in demo mode. By default: 12:00, a full battery that is not charging, full Wi-Fi and
+// mobile signal, and no notification icons.
+#[derive(Default, Deserialize)]
+pub struct DemoModeOptions {
+    // `HHMM`.
+    pub clock: Option<String>,
+
+    pub battery_level: Option<u8>,
+
+    #[serde(default)] pub charging: bool,
+
+    // From 0 to 4 bars.
+    pub signal_level: Option<u8>,
+
+    #[serde(default)] pub show_notifications: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DemoModeRequest {
+    pub enabled: bool,
+
+    #[serde(flatten)] pub options: DemoModeOptions,
+}
+
+// Returns the `sysui_demo_allowed` setting the device had before, `None` when it was not set.
+pub async fn adb_enter_demo_mode(configuration: &Configuration, device_id: &str, options: &DemoModeOptions) -> Result<Option<String>, DemoModeError> {
+    let commands = demo_mode_commands(options)?;
+
+    let previous_allowed = run_shell(configuration, device_id, "settings get global sysui_demo_allowed")
+    .await
+    .map_err(DemoModeError::Shell)?;
+
+    run_shell(configuration, device_id, &commands.join(" && "))
+    .await
+    .map_err(DemoModeError::Shell)?;
+
+    Ok(parse_allowed(&previous_allowed))
+}
+
+pub async fn adb_exit_demo_mode(configuration: &Configuration, device_id: &str) -> Result<(), DemoModeError> {
+    run_shell(configuration, device_id, &demo_command("exit", &[]))
+    .await
+    .map_err(DemoModeError::Shell)?;
+
+    Ok(())
+}
+
+// Called once demo mode is left, with the value `adb_enter_demo_mode` returned.
+pub async fn adb_restore_demo_allowed(configuration: &Configuration, device_id: &str, previous_allowed: Option<&str>) -> Result<(), DemoModeError> {
+    run_shell(configuration, device_id, &restore_allowed_command(previous_allowed))
+    .await
+    .map_err(DemoModeError::Shell)?;
+
+    Ok(())
+}
+
+// Settings that were never set are printed as `null`.
+fn parse_allowed(output: &str) -> Option<String> {
+    let value = output.trim();
+
+    (value != "null" && !value.is_empty()).then(|| value.to_string())
+}
+
+fn restore_allowed_command(previous_allowed: Option<&str>) -> String {
+    match previous_allowed {
+        Some(value) => format!("settings put global sysui_demo_allowed {}", quote(value)),
+        None        => "settings delete global sysui_demo_allowed > /dev/null".to_string(),
+    }
+}
+
+// SystemUI ignores demo mode commands until they are allowed in the settings.
+fn demo_mode_commands(options: &DemoModeOptions) -> Result<[String; 7], DemoModeError> {
+    let clock         = options.clock.as_deref().unwrap_or(DEFAULT_CLOCK);
+    let battery_level = options.battery_level.unwrap_or(DEFAULT_BATTERY_LEVEL);
+    let signal_level  = options.signal_level.unwrap_or(DEFAULT_SIGNAL_LEVEL);
+
+    if !CLOCK_REGEXP.is_match(clock) {
+        return Err(DemoModeError::InvalidOption(format!("clock: {clock}")));
+    }
+
+    if battery_level > 100 {
+        return Err(DemoModeError::InvalidOption(format!("battery_level: {battery_level}")));
+    }
+
+    if signal_level > 4 {
+        return Err(DemoModeError::InvalidOption(format!("signal_level: {signal_level}")));
+    }
+
+    Ok([
+        "settings put global sysui_demo_allowed 1".to_string(),
+        demo_command("enter", &[]),
+        demo_command("clock", &[("hhmm", clock)]),
+        demo_command("battery", &[("level", &battery_level.to_string()), ("plugged", &options.charging.to_string())]),
+        demo_command("network", &[("wifi", "show"), ("level", &signal_level.to_string())]),
+        demo_command("network", &[("mobile", "show"), ("datatype", "none"), ("level", &signal_level.to_string())]),
+        demo_command("notifications", &[("visible", &options.show_notifications.to_string())]),
+    ])
+}
+
+fn demo_command(command: &str, extras: &[(&str, &str)]) -> String {
+    let extras = extras
+    .iter()
+    .map(|(key, value)| format!(" -e {key} {}", quote(value)))
+    .collect::<String>();
+
+    format!("am broadcast -a com.android.systemui.demo -e command {command}{extras} > /dev/null")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demo_mode_commands, parse_allowed, restore_allowed_command, DemoModeError, DemoModeOptions};
+
+    #[test]
+    fn builds_default_demo_mode_commands() {
+        let commands = demo_mode_commands(&DemoModeOptions::default()).ok().unwrap();
+
+        assert_eq!(commands[0], "settings put global sysui_demo_allowed 1");
+        assert_eq!(commands[2], "am broadcast -a com.android.systemui.demo -e command clock -e hhmm '1200' > /dev/null");
+        assert_eq!(commands[3], "am broadcast -a com.android.systemui.demo -e command battery -e level '100' -e plugged 'false' > /dev/null");
+        assert_eq!(commands[6], "am broadcast -a com.android.systemui.demo -e command notifications -e visible 'false' > /dev/null");
+    }
+
+    #[test]
+    fn rejects_invalid_demo_mode_options() {
+        let invalid_options = [
+            DemoModeOptions { clock: Some("2400".to_string()), ..DemoModeOptions::default() },
+            DemoModeOptions { clock: Some("12:00".to_string()), ..DemoModeOptions::default() },
+            DemoModeOptions { battery_level: Some(101), ..DemoModeOptions::default() },
+            DemoModeOptions { signal_level: Some(5), ..DemoModeOptions::default() },
+        ];
+
+        for options in invalid_options {
+            assert!(matches!(demo_mode_commands(&options), Err(DemoModeError::InvalidOption(_))));
+        }
+
+        assert!(demo_mode_commands(&DemoModeOptions { clock: Some("0959".to_string()), ..DemoModeOptions::default() }).is_ok());
+    }
+
+    #[test]
+    fn restores_the_previous_demo_mode_allowance() {
+        assert_eq!(parse_allowed("null\n"), None);
+        assert_eq!(parse_allowed("0\n").as_deref(), Some("0"));
+
+        assert_eq!(restore_allowed_command(None), "settings delete global sysui_demo_allowed > /dev/null");
+        assert_eq!(restore_allowed_command(Some("0")), "settings put global sysui_demo_allowed '0'");
+    }
+}
@@ -1,87 +1,72 @@
-use std::{collections::BTreeMap, net::IpAddr, time::Duration};
+use std::{collections::BTreeMap, net::IpAddr, sync::LazyLock};
 
 use regex::Regex;
-use tokio::{process::Command, time::timeout};
 
-use crate::{common::device::{Device, DeviceListingError}, core::Configuration};
+use crate::{common::device::{Device, DeviceListingError, DeviceListingOptions, DeviceState, MarketingName, NetworkAddress, Transport}, core::{find_marketing_name, Configuration, DeviceCatalog, DeviceProfiles}};
 
-#[derive(Clone)]
-enum ScanResult {
-    IpResult(IpResult),
-    UsbResult(UsbResult),
-}
+use super::{mdns::{adb_mdns_services, parse_mdns_device_id, MdnsServiceType}, network::{find_network_addresses, network_address}, process::run_adb, target::{device_arguments, transport_device_id}};
+
+static DETAIL_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(product|model|device|transport_id):(\S+)").unwrap());
 
+// One line of `adb devices -l`.
 #[derive(Clone)]
-struct UsbResult {
-    pub id: String,
-    pub addresses: Vec<IpAddr>
+struct ListingEntry {
+    pub serial: String,
+    pub state: DeviceState,
+    pub product: Option<String>,
+    pub model: Option<String>,
+    pub device: Option<String>,
+    pub transport_id: Option<u64>,
 }
 
-#[derive(Clone)]
-struct IpResult {
+struct ScannedTransport {
     pub id: String,
-    pub ip: IpAddr,
-    pub is_offline: bool,
+    pub entry: ListingEntry,
+    pub is_remote: bool,
+    pub is_unresponsive: bool,
+    pub addresses: Vec<NetworkAddress>,
+    pub properties: Option<DeviceProperties>,
 }
 
-impl ScanResult {
-    pub fn id(&self) -> &str {
-        match self {
-            ScanResult::IpResult(result)  => &result.id,
-            ScanResult::UsbResult(result) => &result.id,
-        }
-    }
-
-    pub fn to_device(&self, model_query_result: Result<String, DeviceListingError>, aliases: &BTreeMap<String, String>) -> Device {
-        let mut force_offline = false;
-
-        let model = match model_query_result {
-            Ok(model_name) => Some(model_name),
-
-            Err(DeviceListingError::DeviceUnresponsive) => {
-                force_offline = true;
+// Queried from the device itself, as `adb devices -l` only knows about the model and device names.
+struct DeviceProperties {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub device: Option<String>,
+    pub hardware_serial: Option<String>,
+}
 
-                None
-            }
+enum RemoteId<'a> {
+    Address(IpAddr),
+    MdnsService(&'a str),
+}
 
-            Err(_) => None,
-        };
+impl ScannedTransport {
+    pub fn is_available(&self) -> bool {
+        self.entry.state == DeviceState::Device && !self.is_unresponsive
+    }
 
-        match &self {
-            ScanResult::IpResult(ip_result) => Device {
-                model,
-
-                is_remote: true,
-                id: ip_result.id.clone(),
-                known_ips: vec![ip_result.ip],
-                is_offline: force_offline || ip_result.is_offline,
-                alias: aliases.get(&ip_result.id).map(|alias| alias.to_string()),
-            },
-
-            ScanResult::UsbResult(usb_result) => Device {
-                model,
-                is_remote: false,
-                is_offline: false,
-                id: usb_result.id.clone(),
-                known_ips: usb_result.addresses.clone(),
-                alias: aliases.get(&usb_result.id).map(|alias| alias.to_string()),
-            },
+    pub fn to_transport(&self) -> Transport {
+        Transport {
+            id:           self.id.clone(),
+            serial:       self.entry.serial.clone(),
+            state:        self.entry.state.clone(),
+            is_remote:    self.is_remote,
+            is_offline:   !self.is_available(),
+            transport_id: self.entry.transport_id,
         }
     }
 }
 
-pub async fn adb_devices(configuration: &Configuration) -> Result<Vec<Device>, DeviceListingError> {
+// All transports of a physical device (USB, TCP, mDNS) are merged into one device, grouped by hardware serial. Marketing
+// names are looked up in the override catalog first, when there is one.
+pub async fn adb_devices(configuration: &Configuration, options: &DeviceListingOptions, catalog_overrides: Option<&DeviceCatalog>) -> Result<Vec<Device>, DeviceListingError> {
     let adb_command = configuration
     .adb_command
     .as_deref()
     .ok_or(DeviceListingError::DebugBridgePathMissing)?;
 
-    let output = Command
-    ::new(adb_command)
-    .arg("devices")
-    .output()
-    .await
-    .map_err(|error| DeviceListingError::CannotRunProcess(error.to_string()))?;
+    let output = run_adb(adb_command, &["devices", "-l"], &configuration.timeouts.device_listing).await?;
 
     if !output.status.success() {
         return Err(DeviceListingError::BadExitCode(output.status.code()));
@@ -89,184 +74,480 @@ pub async fn adb_devices(configuration: &Configuration) -> Result<Vec<Device>, D
 
     let output = String::from_utf8_lossy(&output.stdout);
 
-    let result_futures = output
+    let entries = output
     .trim_end()
     .split("\n")
     .skip(1)
-    .map(|line| parse_line(adb_command, line.clone()));
+    .filter_map(parse_line)
+    .collect::<Vec<_>>();
 
-    let results = futures
-    ::future
-    ::join_all(result_futures)
-    .await
+    // Operations on a device reported twice under the same serial (common with cheap clones) target its transport instead.
+    let ids = entries
     .iter()
-    .filter_map(|result| result.as_ref().ok())
-    .map(|result| result.clone())
-    .collect::<Vec<ScanResult>>();
+    .map(|entry| {
+        let serial_count = entries
+        .iter()
+        .filter(|other| other.serial == entry.serial)
+        .count();
+
+        match (serial_count, entry.transport_id) {
+            (2.., Some(transport_id)) => transport_device_id(transport_id),
+            _                         => entry.serial.clone(),
+        }
+    })
+    .collect::<Vec<_>>();
 
-    let model_name_queries = futures
+    // Transports are merged by the hardware serial devices report, which is only worth asking for when some are remote.
+    let should_query_properties = options.query_devices || entries
+    .iter()
+    .any(|entry| try_parse_remote_id(&entry.serial).is_some());
+
+    let mut transports = futures
     ::future
     ::join_all(
-        results
-        .iter()
-        .map(|result| find_device_model(adb_command, result.id()))
+        entries
+        .into_iter()
+        .zip(ids)
+        .map(|(entry, id)| scan_transport(configuration, adb_command, entry, id, should_query_properties, options.query_devices))
     )
     .await;
 
-    let remote_devices = results
-    .iter()
-    .filter_map(|result| {
-        if let ScanResult::IpResult(ip_result) = result {
-            return Some((ip_result.ip, ip_result));
-        }
-
-        None
-    })
-    .collect::<BTreeMap<IpAddr, _>>();
+    resolve_mdns_addresses(configuration, &mut transports).await;
 
-    let aliases = results
-    .iter()
-    .filter_map(|result| {
-        if let ScanResult::UsbResult(usb_result) = result {
-            return Some(usb_result);
-        }
+    let profiles = DeviceProfiles::load_or_default(configuration).unwrap_or_else(|error| {
+        log::warn!("Devices are listed without their profiles, which cannot be read: {error:?}");
 
-        None
-    })
-    .filter_map(|usb_result| {
-        usb_result
-        .addresses
-        .iter()
-        .find_map(|address| remote_devices.get(address).map(|ip_result| (usb_result, ip_result)))
-    })
-    .flat_map(|(usb, ip)| [(usb.id.to_string(), ip.id.to_string()), (ip.id.to_string(), usb.id.to_string())])
-    .collect::<BTreeMap<String, String>>();
+        DeviceProfiles::default()
+    });
 
     Ok(
-        results
-        .iter()
-        .zip(model_name_queries)
-        .map(|(result, model_query)| ScanResult::to_device(result, model_query, &aliases))
+        group_transports(transports)
+        .into_iter()
+        .map(|transports| {
+            let mut device = to_device(transports, options, catalog_overrides);
+
+            device.profile = profiles.profiles.get(&device.serial).cloned();
+
+            device
+        })
         .collect()
     )
 }
 
-async fn parse_line(adb_command: &str, line: &str) -> Result<ScanResult, DeviceListingError> {
-    let fields = line.split("\t").collect::<Vec<&str>>();
+// Lines look like `<serial> <state> [usb:<path>] [product:<name>] [model:<name>] [device:<name>] transport_id:<id>`, and
+// the state may contain spaces, as in `no permissions (...)`.
+fn parse_line(line: &str) -> Option<ListingEntry> {
+    let (serial, rest) = line.trim().split_once(char::is_whitespace)?;
+
+    let rest = rest.trim_start();
+
+    let details = DETAIL_REGEXP
+    .captures_iter(rest)
+    .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+    .collect::<BTreeMap<_, _>>();
+
+    Some(ListingEntry {
+        serial:       serial.to_string(),
+        state:        parse_state(rest),
+        product:      details.get("product").cloned(),
+        model:        details.get("model").cloned(),
+        device:       details.get("device").cloned(),
+        transport_id: details.get("transport_id").and_then(|id| id.parse().ok()),
+    })
+}
 
-    if fields.len() < 2 {
-        return Err(DeviceListingError::UnrecognizedDebugBridgeOutput);
+fn parse_state(state_and_details: &str) -> DeviceState {
+    if state_and_details.starts_with("no permissions") {
+        return DeviceState::NoPermissions;
     }
 
-    let id = fields[0].to_string();
+    match state_and_details.split_whitespace().next().unwrap_or_default() {
+        "device"       => DeviceState::Device,
+        "offline"      => DeviceState::Offline,
+        "unauthorized" => DeviceState::Unauthorized,
+        "authorizing"  => DeviceState::Authorizing,
+        "connecting"   => DeviceState::Connecting,
+        "recovery"     => DeviceState::Recovery,
+        "sideload"     => DeviceState::Sideload,
+        "bootloader"   => DeviceState::Bootloader,
+        "rescue"       => DeviceState::Rescue,
+        "host"         => DeviceState::Host,
+
+        other => DeviceState::Unknown(other.to_string()),
+    }
+}
 
-    let is_offline = fields[1].trim().starts_with("offline");
+async fn scan_transport(configuration: &Configuration, adb_command: &str, entry: ListingEntry, id: String, should_query_properties: bool, should_query_addresses: bool) -> ScannedTransport {
+    let remote_id = try_parse_remote_id(&entry.serial);
 
-    if let Some((ip, _)) = try_parse_remote_id(&id) {
-        return Ok(ScanResult::IpResult(IpResult { id, is_offline, ip }));
+    let mut transport = ScannedTransport {
+        is_remote:       remote_id.is_some(),
+        is_unresponsive: false,
+        addresses:       vec![],
+        properties:      None,
+
+        entry: entry.clone(),
+        id,
+    };
+
+    if let Some(RemoteId::Address(ip)) = remote_id {
+        transport.addresses.push(network_address(None, ip, None));
+    }
+
+    // Devices that are not fully available, such as unauthorized ones, do not accept shell commands.
+    if entry.state != DeviceState::Device {
+        return transport;
     }
 
-    let addresses = find_usb_device_ips(adb_command, &id).await?;
+    let id = transport.id.as_str();
+
+    let (properties, addresses) = futures::join!(
+        async {
+            match should_query_properties {
+                true  => find_device_properties(configuration, adb_command, id).await.map(Some),
+                false => Ok(None),
+            }
+        },
+        async {
+            match should_query_addresses && !transport.is_remote {
+                true  => find_network_addresses(configuration, adb_command, id).await,
+                false => Ok(vec![]),
+            }
+        },
+    );
+
+    let results_unresponsive = [
+        properties.as_ref().err(),
+        addresses.as_ref().err(),
+    ];
+
+    transport.is_unresponsive = results_unresponsive
+    .iter()
+    .any(|error| matches!(error, Some(DeviceListingError::DeviceUnresponsive)));
+
+    transport.properties = properties.ok().flatten();
+
+    transport.addresses.extend(addresses.unwrap_or_default());
 
-    Ok(ScanResult::UsbResult(UsbResult { id, addresses }))
+    transport
 }
 
-async fn find_usb_device_ips(adb_command: &str, id: &str) -> Result<Vec<IpAddr>, DeviceListingError> {
-    // Should not be compiled here, but who cares?
-    let ip_regexp = Regex::new(r"inet6? addr:\s*(\d{1,3}(\.\d{1,3}){3})").unwrap();
+// USB serials are reported by the device itself, so they are a good stand-in for transports that cannot be queried.
+fn group_transports(transports: Vec<ScannedTransport>) -> Vec<Vec<ScannedTransport>> {
+    let mut groups = BTreeMap::<String, Vec<ScannedTransport>>::new();
 
-    let output = Command
-    ::new(adb_command)
-    .args(&["-s", id, "shell", "ifconfig | grep 'inet'"])
-    .output()
-    .await
-    .map_err(|error| DeviceListingError::CannotRunProcess(error.to_string()))?;
+    for transport in transports {
+        let hardware_serial = transport
+        .properties
+        .as_ref()
+        .and_then(|properties| properties.hardware_serial.clone());
 
-    Ok(
-        String
-        ::from_utf8_lossy(&output.stdout)
-        .trim()
-        .split("\n")
-        .map(str::trim)
-        .filter_map(|line| {
-            ip_regexp
-            .captures(line)
-            .and_then(|captures| {
-                captures
-                .get(1)
-                .map(|capture| capture.as_str().parse::<IpAddr>().ok())
-            })
-        })
-        .map(Option::unwrap)
-        .filter(|&address| !address.is_loopback())
-        .collect()
-    )
+        let key = match (hardware_serial, transport.is_remote) {
+            (Some(serial), _) => serial,
+            (None, false)     => transport.entry.serial.clone(),
+            (None, true)      => format!("remote:{}", transport.id),
+        };
+
+        groups.entry(key).or_default().push(transport);
+    }
+
+    // A physical device has a single USB transport: groups with more are clones sharing a serial, and stay apart.
+    groups
+    .into_values()
+    .flat_map(|group| {
+        match group.iter().filter(|transport| !transport.is_remote).count() {
+            0 | 1 => vec![group],
+            _     => group.into_iter().map(|transport| vec![transport]).collect(),
+        }
+    })
+    .collect()
+}
+
+// Operations go through the preferred transport: available ones first, then USB before network.
+fn to_device(mut transports: Vec<ScannedTransport>, options: &DeviceListingOptions, catalog_overrides: Option<&DeviceCatalog>) -> Device {
+    transports.sort_by_key(|transport| (!transport.is_available(), transport.is_remote));
+
+    let preferred = &transports[0];
+
+    let find_detail = |detail: fn(&ScannedTransport) -> Option<String>| transports.iter().find_map(detail);
+
+    let find_property = |property: fn(&DeviceProperties) -> Option<String>| transports
+    .iter()
+    .find_map(|transport| transport.properties.as_ref().and_then(property));
+
+    let manufacturer = find_property(|properties| properties.manufacturer.clone());
+
+    let marketing_name = match find_property(|properties| properties.model.clone()).or_else(|| find_detail(|transport| transport.entry.model.clone())) {
+        None => MarketingName::UnknownModel,
+
+        Some(model) => {
+            let device = find_property(|properties| properties.device.clone())
+            .or_else(|| find_detail(|transport| transport.entry.device.clone()));
+
+            match find_marketing_name(catalog_overrides, manufacturer.as_deref(), &model, device.as_deref()) {
+                Some(name) => MarketingName::Found { name },
+                None       => MarketingName::NotInCatalog,
+            }
+        },
+    };
+
+    // Described as "samsung SM-S911B", which is only known when the device was queried.
+    let queried_model = [manufacturer, find_property(|properties| properties.model.clone())]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let mut network_addresses = transports
+    .iter()
+    .flat_map(|transport| transport.addresses.iter().cloned())
+    .filter(|address| options.include_link_local || !address.is_link_local)
+    .collect::<Vec<_>>();
+
+    // Addresses found on the device itself carry the interface name, and win over those parsed from transport ids.
+    network_addresses.sort_by_key(|address| (address.address, address.interface.is_none()));
+    network_addresses.dedup_by_key(|address| address.address);
+
+    Device {
+        known_ips: network_addresses.iter().map(|address| address.address).collect(),
+
+        network_addresses,
+
+        id:           preferred.id.clone(),
+        state:        preferred.entry.state.clone(),
+        is_remote:    preferred.is_remote,
+        is_offline:   !preferred.is_available(),
+        transport_id: preferred.entry.transport_id,
+        serial:       find_property(|properties| properties.hardware_serial.clone()).unwrap_or_else(|| preferred.entry.serial.clone()),
+        profile:      None,
+        model:        (!queried_model.is_empty()).then(|| queried_model.join(" ")),
+        listed_model: find_detail(|transport| transport.entry.model.clone()),
+        marketing_name,
+        product:      find_detail(|transport| transport.entry.product.clone()),
+        device:       find_detail(|transport| transport.entry.device.clone()),
+        transports:   transports.iter().map(ScannedTransport::to_transport).collect(),
+    }
 }
 
-fn try_parse_remote_id(field: &str) -> Option<(IpAddr, u16)> {
-    let parts = field.split(':').collect::<Vec<&str>>();
+// Each property is printed on its own line, empty when the property is not set.
+async fn find_device_properties(configuration: &Configuration, adb_command: &str, device_id: &str) -> Result<DeviceProperties, DeviceListingError> {
+    let script = "getprop ro.product.manufacturer; getprop ro.product.model; getprop ro.product.device; getprop ro.serialno; getprop ro.boot.serialno";
 
-    if parts.len() != 2 {
-        return None;
+    let output = run_adb(adb_command, &device_arguments(device_id, &["shell", script]), &configuration.timeouts.device_properties).await?;
+
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    let values = output
+    .lines()
+    .map(|line| Some(line.trim().to_string()).filter(|value| !value.is_empty()))
+    .collect::<Vec<_>>();
+
+    if values.len() < 5 {
+        return Err(DeviceListingError::UnrecognizedDebugBridgeOutput);
     }
 
-    let port = parts[1].parse::<u16>().ok()?;
+    Ok(DeviceProperties {
+        manufacturer:    values[0].clone(),
+        model:           values[1].clone(),
+        device:          values[2].clone(),
+        hardware_serial: values[3].clone().or_else(|| values[4].clone()),
+    })
+}
 
-    let address = parts[0].parse::<IpAddr>().ok()?;
+// Remote transports are listed as `192.168.1.20:5555`, `[fe80::1]:5555` or `[fe80::1%wlan0]:5555`.
+fn try_parse_remote_id(field: &str) -> Option<RemoteId<'_>> {
+    if let Some(service_name) = parse_mdns_device_id(field) {
+        return Some(RemoteId::MdnsService(service_name));
+    }
 
-    return Some((address, port));
+    let (host, port) = field.rsplit_once(':')?;
+
+    port.parse::<u16>().ok()?;
+
+    let host = match host.strip_prefix('[').and_then(|host| host.strip_suffix(']')) {
+        Some(host) => host.split('%').next().unwrap_or(host),
+        None       => host,
+    };
+
+    let address = host.parse::<IpAddr>().ok()?;
+
+    Some(RemoteId::Address(address))
 }
 
-async fn find_device_model(adb_command: &str, device_id: &str) -> Result<String, DeviceListingError> {
-    let process_task = Command
-    ::new(adb_command)
-    .args(&["-s", device_id, "shell", "getprop"])
-    .output();
+// Devices connected through mDNS are listed under their service name, and their address is only known to the discovery.
+async fn resolve_mdns_addresses(configuration: &Configuration, transports: &mut [ScannedTransport]) {
+    let has_unresolved_addresses = transports
+    .iter()
+    .any(|transport| matches!(try_parse_remote_id(&transport.entry.serial), Some(RemoteId::MdnsService(_))));
 
-    let result = timeout(Duration::from_secs(1), process_task).await;
+    if !has_unresolved_addresses {
+        return;
+    }
 
-    let output = match result {
-        Ok(Ok(output)) => output,
-        Err(_)         => return Err(DeviceListingError::DeviceUnresponsive),
-        Ok(Err(error)) => return Err(DeviceListingError::CannotRunProcess(error.to_string())),
+    let Ok(services) = adb_mdns_services(configuration).await else {
+        return;
     };
 
-    let properties = String
-    ::from_utf8_lossy(&output.stdout)
-    .trim()
-    .split("\n")
-    .filter_map(|line| {
-        let (raw_name, raw_value) = line.trim().split_once(":")?;
+    for transport in transports.iter_mut() {
+        let Some(RemoteId::MdnsService(service_name)) = try_parse_remote_id(&transport.entry.serial) else {
+            continue;
+        };
+
+        let address = services
+        .iter()
+        .find(|service| service.service_type != MdnsServiceType::Pairing && service.name == service_name)
+        .map(|service| service.address);
+
+        transport.addresses.extend(address.map(|address| network_address(None, address, None)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::{adb::target::transport_device_id, common::device::{DeviceListingOptions, DeviceState}};
+
+    use super::{group_transports, parse_line, to_device, try_parse_remote_id, DeviceProperties, RemoteId, ScannedTransport};
+
+    // Captured from a host with a phone connected over USB and Wi-Fi, an emulator, and a phone missing udev rules.
+    const DEVICES_OUTPUT: &str = "\
+List of devices attached
+R58M12ABCDE            device usb:1-1 product:beyond1ltexx model:SM_G973F device:beyond1 transport_id:3
+192.168.1.20:5555      device product:beyond1ltexx model:SM_G973F device:beyond1 transport_id:5
+[fe80::a1b2:c3ff:fed4:e5f6%wlan0]:5555 offline transport_id:6
+adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp device product:beyond1ltexx model:SM_G973F device:beyond1 transport_id:7
+emulator-5554          unauthorized transport_id:1
+0123456789ABCDEF       no permissions (user in plugdev group; are your udev rules wrong?); see [http://developer.android.com/tools/device.html] usb:1-2 transport_id:2
+";
+
+    fn remote_address(id: &str) -> Option<IpAddr> {
+        match try_parse_remote_id(id) {
+            Some(RemoteId::Address(address)) => Some(address),
+            _                                => None,
+        }
+    }
+
+    #[test]
+    fn parses_adb_devices_output() {
+        let entries = DEVICES_OUTPUT
+        .lines()
+        .skip(1)
+        .filter_map(parse_line)
+        .collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), 6);
+
+        assert_eq!(entries[0].serial, "R58M12ABCDE");
+        assert!(entries[0].state == DeviceState::Device);
+        assert_eq!(entries[0].product.as_deref(), Some("beyond1ltexx"));
+        assert_eq!(entries[0].model.as_deref(), Some("SM_G973F"));
+        assert_eq!(entries[0].device.as_deref(), Some("beyond1"));
+        assert_eq!(entries[0].transport_id, Some(3));
+
+        assert_eq!(entries[2].serial, "[fe80::a1b2:c3ff:fed4:e5f6%wlan0]:5555");
+        assert!(entries[2].state == DeviceState::Offline);
+
+        assert!(entries[4].state == DeviceState::Unauthorized);
+        assert_eq!(entries[4].model, None);
+
+        assert!(entries[5].state == DeviceState::NoPermissions);
+        assert_eq!(entries[5].transport_id, Some(2));
+    }
+
+    #[test]
+    fn recognizes_remote_ids() {
+        assert_eq!(remote_address("192.168.1.20:5555"), Some("192.168.1.20".parse().unwrap()));
+        assert_eq!(remote_address("[2a01:e0a:1f2::42]:5555"), Some("2a01:e0a:1f2::42".parse().unwrap()));
+        assert_eq!(remote_address("[fe80::a1b2:c3ff:fed4:e5f6%wlan0]:5555"), Some("fe80::a1b2:c3ff:fed4:e5f6".parse().unwrap()));
 
-        let property = {
-            let mut characters = raw_name.chars();
+        assert!(matches!(try_parse_remote_id("adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp"), Some(RemoteId::MdnsService("adb-R58M12ABCDE-AbCdEf"))));
 
-            characters.next();
-            characters.next_back();
+        assert!(try_parse_remote_id("R58M12ABCDE").is_none());
+        assert!(try_parse_remote_id("emulator-5554").is_none());
+        assert!(try_parse_remote_id("192.168.1.20:adb").is_none());
+    }
 
-            characters.as_str()
+    #[test]
+    fn merges_the_usb_and_network_transports_of_a_device() {
+        let transport = |line: &str, hardware_serial: Option<&str>| {
+            let entry = parse_line(line).unwrap();
+
+            ScannedTransport {
+                id:              entry.serial.clone(),
+                is_remote:       try_parse_remote_id(&entry.serial).is_some(),
+                is_unresponsive: false,
+                addresses:       vec![],
+
+                properties: Some(DeviceProperties {
+                    manufacturer:    Some("samsung".to_string()),
+                    model:           Some("SM-G973F".to_string()),
+                    device:          Some("beyond1".to_string()),
+                    hardware_serial: hardware_serial.map(str::to_string),
+                }),
+
+                entry,
+            }
         };
 
-        let value = {
-            let mut characters = raw_value.trim().chars();
+        let groups = group_transports(vec![
+            transport("R58M12ABCDE device usb:1-1 model:SM_G973F transport_id:3", Some("R58M12ABCDE")),
+            transport("[2a01:e0a:1f2::42]:5555 device model:SM_G973F transport_id:5", Some("R58M12ABCDE")),
+            transport("adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp device model:SM_G973F transport_id:7", Some("R58M12ABCDE")),
+            transport("emulator-5554 device model:sdk_gphone64_x86_64 transport_id:1", Some("EMULATOR34X1X5X0")),
+        ]);
 
-            characters.next();
-            characters.next_back();
+        let ids = groups
+        .iter()
+        .map(|group| group.iter().map(|transport| transport.id.as_str()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+        assert_eq!(ids, [
+            vec!["emulator-5554"],
+            vec!["R58M12ABCDE", "[2a01:e0a:1f2::42]:5555", "adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp"],
+        ]);
+    }
 
-            characters.as_str()
+    #[test]
+    fn keeps_clones_sharing_a_serial_apart() {
+        let transport = |transport_id: u64| {
+            let entry = parse_line(&format!("0123456789ABCDEF device usb:1-{transport_id} transport_id:{transport_id}")).unwrap();
+
+            ScannedTransport { id: transport_device_id(transport_id), is_remote: false, is_unresponsive: false, addresses: vec![], properties: None, entry }
         };
 
-        Some((property.to_string(), value.to_string()))
-    })
-    .collect::<BTreeMap::<String, String>>();
+        assert_eq!(group_transports(vec![transport(1), transport(2)]).len(), 2);
+    }
 
-    let parts = [properties.get("ro.product.manufacturer"), properties.get("ro.product.model")]
-    .iter()
-    .filter_map(|value| value.map(|part| part.to_string()))
-    .collect::<Vec<_>>();
+    #[test]
+    fn describes_the_model_reported_by_the_device() {
+        let transport = |properties: Option<DeviceProperties>| ScannedTransport {
+            id:              "R58M12ABCDE".to_string(),
+            is_remote:       false,
+            is_unresponsive: false,
+            addresses:       vec![],
+            entry:           parse_line("R58M12ABCDE device usb:1-1 product:beyond1ltexx model:SM_G973F device:beyond1 transport_id:3").unwrap(),
+
+            properties,
+        };
+
+        let properties = DeviceProperties {
+            manufacturer:    Some("samsung".to_string()),
+            model:           Some("SM-G973F".to_string()),
+            device:          Some("beyond1".to_string()),
+            hardware_serial: Some("R58M12ABCDE".to_string()),
+        };
+
+        let queried = to_device(vec![transport(Some(properties))], &DeviceListingOptions::default(), None);
+
+        assert_eq!(queried.model.as_deref(), Some("samsung SM-G973F"));
+        assert_eq!(queried.listed_model.as_deref(), Some("SM_G973F"));
+
+        // Devices that were not queried are only described by `adb devices -l`.
+        let listed = to_device(vec![transport(None)], &DeviceListingOptions::default(), None);
 
-    match parts.is_empty() {
-        false => Ok(parts.join(" ")),
-        true  => Err(DeviceListingError::UnrecognizedDebugBridgeOutput),
+        assert_eq!(listed.model, None);
+        assert_eq!(listed.listed_model.as_deref(), Some("SM_G973F"));
+        assert_eq!(listed.product.as_deref(), Some("beyond1ltexx"));
+        assert_eq!(listed.serial, "R58M12ABCDE");
     }
 }
@@ -0,0 +1,129 @@
+use regex::Regex;
+
+use crate::{common::{device::{Device, DeviceListingOptions}, fan_out::{DeviceSelector, DeviceTargets, ResolveTargetsError}}, core::Configuration};
+
+use super::device::adb_devices;
+
+pub async fn adb_resolve_targets(configuration: &Configuration, targets: &DeviceTargets) -> Result<Vec<String>, ResolveTargetsError> {
+    let selector = match targets {
+        DeviceTargets::Ids(ids)           => return Ok(ids.clone()),
+        DeviceTargets::Selector(selector) => selector,
+    };
+
+    let model_regex = match selector {
+        DeviceSelector::ModelRegex(pattern) => Some(
+            Regex
+            ::new(pattern)
+            .map_err(|error| ResolveTargetsError::InvalidModelRegex(error.to_string()))?
+        ),
+
+        _ => None,
+    };
+
+    // Models are matched against what devices report about themselves.
+    let options = DeviceListingOptions { query_devices: matches!(selector, DeviceSelector::ModelRegex(_)), ..DeviceListingOptions::default() };
+
+    let devices = adb_devices(configuration, &options, None)
+    .await
+    .map_err(ResolveTargetsError::DeviceListingFailed)?;
+
+    let device_ids = select_device_ids(devices, selector, model_regex.as_ref());
+
+    match device_ids.is_empty() {
+        false => Ok(device_ids),
+        true  => Err(ResolveTargetsError::NoMatchingDevice),
+    }
+}
+
+// `model_regex` is the compiled pattern of `ModelRegex` selectors.
+fn select_device_ids(devices: Vec<Device>, selector: &DeviceSelector, model_regex: Option<&Regex>) -> Vec<String> {
+    devices
+    .into_iter()
+    .filter_map(|device| match selector {
+        DeviceSelector::AllOnline => (!device.is_offline).then_some(device.id),
+
+        // Devices reachable over the network are targeted through their network transport, even when also plugged in.
+        DeviceSelector::AllRemote => device
+        .transports
+        .into_iter()
+        .find(|transport| transport.is_remote && !transport.is_offline)
+        .map(|transport| transport.id),
+
+        DeviceSelector::ModelRegex(_) => {
+            let matches_model = device
+            .model
+            .as_deref()
+            .is_some_and(|model| model_regex.is_some_and(|regex| regex.is_match(model)));
+
+            (matches_model && !device.is_offline).then_some(device.id)
+        },
+
+        DeviceSelector::Label(label) => device.has_label(label).then_some(device.id),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::common::{device::{Device, DeviceProfile, DeviceState, MarketingName, Transport}, fan_out::DeviceSelector};
+
+    use super::select_device_ids;
+
+    fn transport(id: &str, is_remote: bool, is_offline: bool) -> Transport {
+        Transport { id: id.to_string(), serial: id.to_string(), state: DeviceState::Device, is_remote, is_offline, transport_id: None }
+    }
+
+    fn device(id: &str, model: &str, is_offline: bool, transports: Vec<Transport>, labels: &[&str]) -> Device {
+        Device {
+            id:                id.to_string(),
+            serial:            id.to_string(),
+            state:             DeviceState::Device,
+            is_remote:         false,
+            model:             Some(model.to_string()),
+            listed_model:      None,
+            marketing_name:    MarketingName::NotInCatalog,
+            profile:           Some(DeviceProfile { labels: labels.iter().map(|label| label.to_string()).collect(), ..DeviceProfile::default() }),
+            product:           None,
+            device:            None,
+            transport_id:      None,
+            known_ips:         vec![],
+            network_addresses: vec![],
+
+            is_offline,
+            transports,
+        }
+    }
+
+    fn devices() -> Vec<Device> {
+        vec![
+            device("R58M12ABCDE", "samsung SM-S911B", false, vec![transport("R58M12ABCDE", false, false), transport("192.168.1.20:5555", true, false)], &["smoke"]),
+            device("R58N98ZYXWV", "samsung SM-S918B", true, vec![transport("R58N98ZYXWV", false, true)], &["smoke"]),
+            device("emulator-5554", "Google sdk_gphone64_x86_64", false, vec![transport("emulator-5554", false, false)], &[]),
+            device("192.168.1.30:5555", "Google Pixel 8", false, vec![transport("192.168.1.30:5555", true, false)], &["nightly"]),
+        ]
+    }
+
+    #[test]
+    fn selects_online_and_remote_devices() {
+        assert_eq!(select_device_ids(devices(), &DeviceSelector::AllOnline, None), ["R58M12ABCDE", "emulator-5554", "192.168.1.30:5555"]);
+
+        // Devices plugged in and reachable over the network are targeted through the network.
+        assert_eq!(select_device_ids(devices(), &DeviceSelector::AllRemote, None), ["192.168.1.20:5555", "192.168.1.30:5555"]);
+    }
+
+    #[test]
+    fn selects_online_devices_by_model() {
+        let selector    = DeviceSelector::ModelRegex("^samsung SM-S91".to_string());
+        let model_regex = Regex::new("^samsung SM-S91").unwrap();
+
+        assert_eq!(select_device_ids(devices(), &selector, Some(&model_regex)), ["R58M12ABCDE"]);
+    }
+
+    #[test]
+    fn selects_devices_by_label() {
+        assert_eq!(select_device_ids(devices(), &DeviceSelector::Label("smoke".to_string()), None), ["R58M12ABCDE", "R58N98ZYXWV"]);
+        assert!(select_device_ids(devices(), &DeviceSelector::Label("release".to_string()), None).is_empty());
+    }
+}
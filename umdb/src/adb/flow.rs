@@ -0,0 +1,231 @@
+use std::{fs, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{common::{fan_out::{fan_out, DeviceTargets, ResolveTargetsError}, flow::{Flow, FlowResult, FlowStep, StepResult, StepStatus}, input::{InputAction, InputEvent}, links::DeepLinkOptions, ui::{UiAction, UiActionRequest, UiSelector}}, core::Configuration};
+
+use super::{activity::{read_foreground_activity, ActivityError}, fan_out::adb_resolve_targets, input::adb_send_input, links::adb_open_deep_link, logcat::{adb_device_time, adb_logcat_since}, screenshot::adb_screenshot, ui::adb_ui_action};
+
+const DEFAULT_ACTIVITY_TIMEOUT_MS: u64 = 10_000;
+
+const ACTIVITY_POLLING_INTERVAL: Duration = Duration::from_millis(500);
+
+// What a flow run on a device needs to remember from one step to the next.
+struct FlowRun<'a> {
+    configuration: &'a Configuration,
+    device_id: &'a str,
+    artifacts_directory: PathBuf,
+
+    // Device time at which the flow started, for log assertions.
+    started_at_device_time: Option<String>,
+}
+
+// Each device gets its own artifacts directory, under a directory shared by the whole run.
+pub async fn adb_run_flow_on_devices(configuration: &Configuration, flow: &Flow, targets: &DeviceTargets, concurrency: Option<usize>) -> Result<Vec<FlowResult>, ResolveTargetsError> {
+    let device_ids = adb_resolve_targets(configuration, targets).await?;
+
+    let run_directory = configuration
+    .artifacts_directory()
+    .join("flows")
+    .join(unix_time_ms(SystemTime::now()).to_string());
+
+    let results = fan_out(device_ids, concurrency.unwrap_or(configuration.fan_out_concurrency), |device_id| {
+        let artifacts_directory = run_directory.join(file_name(&device_id));
+
+        async move { adb_run_flow(configuration, &device_id, flow, artifacts_directory).await }
+    })
+    .await;
+
+    Ok(results.into_values().collect())
+}
+
+// Steps run until one fails. The screen is then captured, and the remaining steps are skipped.
+pub async fn adb_run_flow(configuration: &Configuration, device_id: &str, flow: &Flow, artifacts_directory: PathBuf) -> FlowResult {
+    let started_at  = SystemTime::now();
+    let has_failed  = |steps: &[StepResult]| steps.iter().any(|step| step.status == StepStatus::Failed);
+    let checks_logs = flow.steps.iter().any(|step| matches!(step, FlowStep::AssertLogNotContains { .. }));
+
+    let run = FlowRun {
+        started_at_device_time: match checks_logs {
+            true  => adb_device_time(configuration, device_id).await.ok(),
+            false => None,
+        },
+
+        configuration,
+        device_id,
+        artifacts_directory,
+    };
+
+    let mut steps = Vec::<StepResult>::with_capacity(flow.steps.len());
+
+    for (index, step) in flow.steps.iter().enumerate() {
+        let name = step.describe();
+
+        if has_failed(&steps) {
+            steps.push(StepResult { name, status: StepStatus::Skipped, duration_ms: 0, error: None, screenshot: None });
+
+            continue;
+        }
+
+        let step_started_at = Instant::now();
+
+        let (status, error, screenshot) = match run_step(&run, index, step).await {
+            Ok(screenshot) => (StepStatus::Passed, None, screenshot),
+            Err(error)     => (StepStatus::Failed, Some(error), run.save_screenshot(index, "failure").await.ok()),
+        };
+
+        steps.push(StepResult {
+            duration_ms: step_started_at.elapsed().as_millis() as u64,
+
+            name,
+            status,
+            error,
+            screenshot,
+        });
+    }
+
+    FlowResult {
+        flow:        flow.name.clone(),
+        device_id:   device_id.to_string(),
+        passed:      !has_failed(&steps),
+        timestamp:   unix_time_ms(started_at),
+        duration_ms: started_at.elapsed().unwrap_or(Duration::ZERO).as_millis() as u64,
+        steps,
+    }
+}
+
+// Returns the screenshot the step took, if any.
+async fn run_step(run: &FlowRun<'_>, index: usize, step: &FlowStep) -> Result<Option<PathBuf>, String> {
+    let configuration = run.configuration;
+    let device_id     = run.device_id;
+
+    match step {
+        FlowStep::OpenLink { url, package, extras } => {
+            let options = DeepLinkOptions { package: package.as_deref(), extras: extras.as_ref() };
+
+            adb_open_deep_link(configuration, device_id, url, &options).await.map_err(describe_error)?;
+        },
+
+        FlowStep::WaitForActivity { activity, timeout_ms } => {
+            let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_ACTIVITY_TIMEOUT_MS));
+
+            run.wait_for_activity(activity, timeout).await?;
+        },
+
+        FlowStep::AssertActivity { activity } => run.wait_for_activity(activity, Duration::ZERO).await?,
+
+        FlowStep::AssertElement { selector, timeout_ms } => run.ui_action(selector, None, *timeout_ms).await?,
+        FlowStep::Tap { selector, timeout_ms }           => run.ui_action(selector, Some(UiAction::Tap), *timeout_ms).await?,
+
+        FlowStep::Type { selector: Some(selector), text } => run.ui_action(selector, Some(UiAction::SetText { text: text.clone() }), None).await?,
+        FlowStep::Type { selector: None, text }           => run.send_input(InputAction::Text { text: text.clone() }).await?,
+
+        FlowStep::PressKey { key } => run.send_input(InputAction::Key { key: key.clone(), long_press: false }).await?,
+
+        FlowStep::Screenshot { name } => {
+            return run
+            .save_screenshot(index, name.as_deref().unwrap_or("screenshot"))
+            .await
+            .map(Some);
+        },
+
+        FlowStep::AssertLogNotContains { pattern } => {
+            let pattern_regexp = Regex::new(pattern).map_err(|error| error.to_string())?;
+
+            let device_time = run
+            .started_at_device_time
+            .as_deref()
+            .ok_or("The device time could not be read when the flow started")?;
+
+            let logs = adb_logcat_since(configuration, device_id, device_time).await.map_err(describe_error)?;
+
+            if let Some(line) = logs.lines().find(|line| pattern_regexp.is_match(line)) {
+                return Err(format!("The log contains: {line}"));
+            }
+        },
+
+        FlowStep::Sleep { duration_ms } => tokio::time::sleep(Duration::from_millis(*duration_ms)).await,
+    }
+
+    Ok(None)
+}
+
+impl FlowRun<'_> {
+    async fn wait_for_activity(&self, activity: &str, timeout: Duration) -> Result<(), String> {
+        let started_at = Instant::now();
+
+        loop {
+            // The screen may be locked, or between two activities.
+            let foreground_activity = match read_foreground_activity(self.configuration, self.device_id).await {
+                Ok(foreground_activity)                  => Some(foreground_activity),
+                Err(ActivityError::NoForegroundActivity) => None,
+                Err(error)                               => return Err(describe_error(error)),
+            };
+
+            if foreground_activity.as_ref().is_some_and(|foreground_activity| foreground_activity.matches(activity)) {
+                return Ok(());
+            }
+
+            if started_at.elapsed() >= timeout {
+                return Err(match foreground_activity {
+                    Some(foreground_activity) => format!("The foreground activity is {}", foreground_activity.component),
+                    None                      => "No activity is in the foreground".to_string(),
+                });
+            }
+
+            tokio::time::sleep(ACTIVITY_POLLING_INTERVAL).await;
+        }
+    }
+
+    async fn ui_action(&self, selector: &UiSelector, action: Option<UiAction>, timeout_ms: Option<u64>) -> Result<(), String> {
+        let request = UiActionRequest { selector: selector.clone(), action, timeout_ms };
+
+        adb_ui_action(self.configuration, self.device_id, &request).await.map_err(describe_error)?;
+
+        Ok(())
+    }
+
+    async fn send_input(&self, action: InputAction) -> Result<(), String> {
+        let event = InputEvent { action, source: None, display_id: None };
+
+        adb_send_input(self.configuration, self.device_id, &event).await.map_err(describe_error)
+    }
+
+    async fn save_screenshot(&self, index: usize, name: &str) -> Result<PathBuf, String> {
+        let image = adb_screenshot(self.configuration, self.device_id).await.map_err(describe_error)?;
+
+        let path = self.artifacts_directory.join(format!("{:02}-{}.png", index + 1, file_name(name)));
+
+        write_artifact(&path, &image).map_err(|error| error.to_string())?;
+
+        Ok(path)
+    }
+}
+
+fn write_artifact(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    fs::write(path, contents)
+}
+
+// Errors are reported as the JSON the REST API would return for them.
+fn describe_error<T: Serialize>(error: T) -> String {
+    serde_json::to_string(&error).unwrap()
+}
+
+fn file_name(name: &str) -> String {
+    name
+    .chars()
+    .map(|character| match character.is_ascii_alphanumeric() || character == '-' {
+        true  => character,
+        false => '_',
+    })
+    .collect()
+}
+
+fn unix_time_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_millis() as u64
+}
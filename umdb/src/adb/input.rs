@@ -0,0 +1,278 @@
+use std::{process::Output, sync::LazyLock, time::{Duration, Instant}};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use regex::Regex;
+
+use crate::{common::input::{InputAction, InputError, InputEvent, InputSource}, core::Configuration};
+
+use super::{process::run_adb, shell::quote, target::device_arguments};
+
+// ADBKeyBoard (https://github.com/senzhk/ADBKeyBoard) types any text it receives through a broadcast.
+const UNICODE_KEYBOARD: &str = "com.android.adbkeyboard/.AdbIME";
+
+const DEFAULT_LONG_PRESS_DURATION_MS: u64 = 1000;
+
+const KEYBOARD_POLLING_INTERVAL: Duration = Duration::from_millis(100);
+
+static KEY_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_]+$").unwrap());
+
+pub async fn adb_send_input(configuration: &Configuration, device_id: &str, event: &InputEvent) -> Result<(), InputError> {
+    // Text typed by a keyboard app goes to the focused window, whatever the source and display.
+    if let InputAction::Text { text } = &event.action {
+        if !text.is_ascii() {
+            return type_unicode_text(configuration, device_id, text).await;
+        }
+    }
+
+    let mut command = vec!["input".to_string()];
+
+    if let Some(display_id) = event.display_id {
+        command.extend(["-d".to_string(), display_id.to_string()]);
+    }
+
+    if let Some(source) = event.source {
+        command.push(source_name(source).to_string());
+    }
+
+    command.extend(input_arguments(&event.action)?);
+
+    run_input_command(configuration, device_id, &command.join(" ")).await
+}
+
+// Deletes the given number of characters at the end of the focused text field.
+pub async fn adb_clear_text(configuration: &Configuration, device_id: &str, length: usize) -> Result<(), InputError> {
+    let command = ["input keyevent KEYCODE_MOVE_END"]
+    .into_iter()
+    .chain(std::iter::repeat_n("KEYCODE_DEL", length))
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    run_input_command(configuration, device_id, &command).await
+}
+
+async fn run_input_command(configuration: &Configuration, device_id: &str, command: &str) -> Result<(), InputError> {
+    let output = run_input_shell(configuration, device_id, command).await?;
+
+    let combined_output = combined_output(&output);
+
+    // Depending on the Android version, `input` reports errors with a zero exit code.
+    if !output.status.success() || combined_output.contains("Error:") || combined_output.contains("Exception") {
+        return Err(InputError::CommandFailed(combined_output));
+    }
+
+    Ok(())
+}
+
+async fn read_input_state(configuration: &Configuration, device_id: &str, command: &str) -> Result<String, InputError> {
+    let output = run_input_shell(configuration, device_id, command).await?;
+
+    match output.status.success() {
+        true  => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => Err(InputError::CommandFailed(combined_output(&output))),
+    }
+}
+
+async fn run_input_shell(configuration: &Configuration, device_id: &str, command: &str) -> Result<Output, InputError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(InputError::DebugBridgePathMissing)?;
+
+    Ok(run_adb(adb_command, &device_arguments(device_id, &["shell", command]), &configuration.timeouts.shell).await?)
+}
+
+fn input_arguments(action: &InputAction) -> Result<Vec<String>, InputError> {
+    let arguments = match action {
+        InputAction::Tap { x, y } => vec!["tap".to_string(), x.to_string(), y.to_string()],
+
+        // A swipe that does not move is how `input` long-presses a point.
+        InputAction::LongPress { x, y, duration_ms } => gesture_arguments(
+            "swipe",
+            [x, y, x, y],
+            &Some(duration_ms.unwrap_or(DEFAULT_LONG_PRESS_DURATION_MS)),
+        ),
+
+        InputAction::Swipe { from_x, from_y, to_x, to_y, duration_ms } => gesture_arguments("swipe", [from_x, from_y, to_x, to_y], duration_ms),
+        InputAction::Drag { from_x, from_y, to_x, to_y, duration_ms }  => gesture_arguments("draganddrop", [from_x, from_y, to_x, to_y], duration_ms),
+
+        // `input text` reads `%s` as a space, and is confused by actual spaces on older versions.
+        InputAction::Text { text } => vec!["text".to_string(), quote(&text.replace(' ', "%s"))],
+
+        InputAction::Key { key, long_press } => {
+            let mut arguments = vec!["keyevent".to_string()];
+
+            if *long_press {
+                arguments.push("--longpress".to_string());
+            }
+
+            arguments.push(key_code(key)?);
+
+            arguments
+        },
+
+        // Only available from Android 13.
+        InputAction::KeyCombination { keys } => {
+            if keys.is_empty() {
+                return Err(InputError::EmptyKeyCombination);
+            }
+
+            let mut arguments = vec!["keycombination".to_string()];
+
+            for key in keys {
+                arguments.push(key_code(key)?);
+            }
+
+            arguments
+        },
+    };
+
+    Ok(arguments)
+}
+
+fn gesture_arguments(command: &str, coordinates: [&u32; 4], duration_ms: &Option<u64>) -> Vec<String> {
+    [command.to_string()]
+    .into_iter()
+    .chain(coordinates.iter().map(|coordinate| coordinate.to_string()))
+    .chain(duration_ms.map(|duration_ms| duration_ms.to_string()))
+    .collect()
+}
+
+// `input text` cannot type characters outside of ASCII: the text is handed to ADBKeyBoard instead, which is made the
+// current keyboard for the time of the broadcast. Keyboards are switched asynchronously, and ADBKeyBoard only listens
+// for the broadcast once bound, so the switch is waited for before the text is sent.
+async fn type_unicode_text(configuration: &Configuration, device_id: &str, text: &str) -> Result<(), InputError> {
+    let keyboards = read_input_state(configuration, device_id, "ime list -a -s").await?;
+
+    if !keyboards.lines().any(|keyboard| keyboard.trim() == UNICODE_KEYBOARD) {
+        return Err(InputError::UnicodeKeyboardMissing);
+    }
+
+    let previous_keyboard = read_input_state(configuration, device_id, "settings get secure default_input_method").await?;
+
+    let keyboard = quote(UNICODE_KEYBOARD);
+
+    run_input_command(configuration, device_id, &format!("ime enable {keyboard} >/dev/null && ime set {keyboard} >/dev/null")).await?;
+
+    let typed = async {
+        wait_for_keyboard(configuration, device_id, UNICODE_KEYBOARD).await?;
+
+        // `am broadcast` returns once receivers have handled the broadcast, by which time the text is committed.
+        run_input_command(configuration, device_id, &format!("am broadcast -a ADB_INPUT_B64 --es msg {} >/dev/null", quote(&STANDARD.encode(text)))).await
+    }
+    .await;
+
+    // The previous keyboard is restored even when typing failed. `null` when the device had no default keyboard.
+    let restore_command = match previous_keyboard.trim() {
+        "" | "null" => "ime reset >/dev/null".to_string(),
+        previous    => format!("ime set {} >/dev/null", quote(previous)),
+    };
+
+    let restored = run_input_command(configuration, device_id, &restore_command).await;
+
+    typed.and(restored)
+}
+
+async fn wait_for_keyboard(configuration: &Configuration, device_id: &str, keyboard: &str) -> Result<(), InputError> {
+    let started_at = Instant::now();
+
+    loop {
+        let state = read_input_state(configuration, device_id, "dumpsys input_method").await?;
+
+        if is_keyboard_bound(&state, keyboard) {
+            return Ok(());
+        }
+
+        if started_at.elapsed() >= configuration.timeouts.shell.timeout() {
+            return Err(InputError::UnicodeKeyboardNotReady);
+        }
+
+        tokio::time::sleep(KEYBOARD_POLLING_INTERVAL).await;
+    }
+}
+
+// `dumpsys input_method` names the current keyboard in `mCurMethodId=`. Until the keyboard service is bound, it prints
+// `mCurMethod=null`, and from Android 10 `mBoundToMethod=false`.
+fn is_keyboard_bound(dumpsys_output: &str, keyboard: &str) -> bool {
+    let fields = dumpsys_output
+    .split_whitespace()
+    .collect::<Vec<_>>();
+
+    fields.contains(&format!("mCurMethodId={keyboard}").as_str()) && !fields.contains(&"mCurMethod=null") && !fields.contains(&"mBoundToMethod=false")
+}
+
+fn key_code(key: &str) -> Result<String, InputError> {
+    if !KEY_REGEXP.is_match(key) {
+        return Err(InputError::InvalidKey(key.to_string()));
+    }
+
+    let key = key.to_ascii_uppercase();
+
+    match key.starts_with("KEYCODE_") || key.chars().all(|character| character.is_ascii_digit()) {
+        true  => Ok(key),
+        false => Ok(format!("KEYCODE_{key}")),
+    }
+}
+
+fn source_name(source: InputSource) -> &'static str {
+    match source {
+        InputSource::Touchscreen     => "touchscreen",
+        InputSource::Touchpad        => "touchpad",
+        InputSource::Touchnavigation => "touchnavigation",
+        InputSource::Stylus          => "stylus",
+        InputSource::Mouse           => "mouse",
+        InputSource::Trackball       => "trackball",
+        InputSource::Dpad            => "dpad",
+        InputSource::Keyboard        => "keyboard",
+        InputSource::Gamepad         => "gamepad",
+        InputSource::Joystick        => "joystick",
+    }
+}
+
+fn combined_output(output: &Output) -> String {
+    format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+    .trim()
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_keyboard_bound, key_code, UNICODE_KEYBOARD};
+
+    // `dumpsys input_method` on Android 13, right after `ime set`, then once ADBKeyBoard is bound.
+    const SWITCHING_DUMP: &str = "\
+Current Input Method Manager state:
+  mCurMethodId=com.android.adbkeyboard/.AdbIME
+  mCurrentSubtype=null
+  mCurSeq=14 mCurClient=ClientState{8c1d2e3 mUid=10152 mPid=4411 mSelfReportedDisplayId=0}
+  mCurFocusedWindow=Window{5f3e2a1 u0 com.example.shop/com.example.shop.SearchActivity} softInputMode=STATE_UNSPECIFIED|ADJUST_RESIZE client=android.os.BinderProxy@7a1b2c3
+  mCurId=com.android.adbkeyboard/.AdbIME mHaveConnection=true mBoundToMethod=false mVisibleBound=false
+  mCurToken=android.os.Binder@a4b5c6d
+  mCurMethod=null
+";
+
+    const BOUND_DUMP: &str = "\
+Current Input Method Manager state:
+  mCurMethodId=com.android.adbkeyboard/.AdbIME
+  mCurrentSubtype=null
+  mCurSeq=14 mCurClient=ClientState{8c1d2e3 mUid=10152 mPid=4411 mSelfReportedDisplayId=0}
+  mCurFocusedWindow=Window{5f3e2a1 u0 com.example.shop/com.example.shop.SearchActivity} softInputMode=STATE_UNSPECIFIED|ADJUST_RESIZE client=android.os.BinderProxy@7a1b2c3
+  mCurId=com.android.adbkeyboard/.AdbIME mHaveConnection=true mBoundToMethod=true mVisibleBound=false
+  mCurToken=android.os.Binder@a4b5c6d
+  mCurMethod=com.android.internal.inputmethod.IInputMethod$Stub$Proxy@e7f8a9b
+";
+
+    #[test]
+    fn waits_for_the_keyboard_to_be_bound() {
+        assert!(!is_keyboard_bound(SWITCHING_DUMP, UNICODE_KEYBOARD));
+        assert!(is_keyboard_bound(BOUND_DUMP, UNICODE_KEYBOARD));
+        assert!(!is_keyboard_bound(BOUND_DUMP, "com.google.android.inputmethod.latin/com.android.inputmethod.latin.LatinIME"));
+    }
+
+    #[test]
+    fn names_key_codes() {
+        assert_eq!(key_code("back").ok(), Some("KEYCODE_BACK".to_string()));
+        assert_eq!(key_code("KEYCODE_DPAD_UP").ok(), Some("KEYCODE_DPAD_UP".to_string()));
+        assert_eq!(key_code("66").ok(), Some("66".to_string()));
+        assert!(key_code("BACK; reboot").is_err());
+    }
+}
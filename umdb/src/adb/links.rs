@@ -1,18 +1,28 @@
-use std::process::Command;
+use crate::{core::Configuration, common::links::{DeepLinkOptions, OpenDeepLinkError, OpenDeepLinkResult}};
 
-use crate::{core::Configuration, common::links::{OpenDeepLinkError, OpenDeepLinkResult}};
+use super::{process::run_adb, shell::quote, target::device_arguments};
 
-pub fn adb_open_deep_link(configuration: &Configuration, device_id: &str, link: &str) -> Result<OpenDeepLinkResult, OpenDeepLinkError> {
+pub async fn adb_open_deep_link(configuration: &Configuration, device_id: &str, link: &str, options: &DeepLinkOptions<'_>) -> Result<OpenDeepLinkResult, OpenDeepLinkError> {
     let adb_command = configuration
     .adb_command
     .as_deref()
     .ok_or(OpenDeepLinkError::DebugBridgePathMissing)?;
 
-    let output = Command
-    ::new(adb_command)
-    .args(["-s", device_id, "shell", "am", "start", "-W", "-a", "android.intent.action.VIEW", "-d", link])
-    .output()
-    .map_err(|error| OpenDeepLinkError::CannotRunProcess(error.to_string()))?;
+    let mut intent_arguments = vec!["-a".to_string(), "android.intent.action.VIEW".to_string(), "-d".to_string(), quote(link)];
+
+    if let Some(package) = options.package {
+        intent_arguments.extend(["-p".to_string(), quote(package)]);
+    }
+
+    for (key, value) in options.extras.into_iter().flatten() {
+        intent_arguments.extend(["--es".to_string(), quote(key), quote(value)]);
+    }
+
+    let mut arguments = vec!["shell", "am", "start", "-W"];
+
+    arguments.extend(intent_arguments.iter().map(String::as_str));
+
+    let output = run_adb(adb_command, &device_arguments(device_id, &arguments), &configuration.timeouts.deep_link).await?;
 
     if !output.status.success() {
         return Err(OpenDeepLinkError::BadExitCode(output.status.code()));
@@ -0,0 +1,43 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::core::Configuration;
+
+use super::shell::{quote, run_shell, ShellError};
+
+static DEVICE_TIME_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}$").unwrap());
+
+// The device clock, in the format `logcat -t` expects. Used to only read what was logged after a given moment.
+pub async fn adb_device_time(configuration: &Configuration, device_id: &str) -> Result<String, ShellError> {
+    let output = run_shell(configuration, device_id, "date +'%m-%d %H:%M:%S.000'").await?;
+
+    parse_device_time(&output).ok_or(ShellError::CommandFailed(output))
+}
+
+// Some shells do not know all `date` formats and print them back as is.
+fn parse_device_time(output: &str) -> Option<String> {
+    let time = output.trim();
+
+    DEVICE_TIME_REGEXP
+    .is_match(time)
+    .then(|| time.to_string())
+}
+
+pub async fn adb_logcat_since(configuration: &Configuration, device_id: &str, device_time: &str) -> Result<String, ShellError> {
+    run_shell(configuration, device_id, &format!("logcat -d -t {}", quote(device_time))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_device_time;
+
+    #[test]
+    fn parses_device_times_in_the_logcat_format() {
+        assert_eq!(parse_device_time("10-19 07:17:36.000\n").as_deref(), Some("10-19 07:17:36.000"));
+
+        assert_eq!(parse_device_time("%m-%d %H:%M:%S.000\n"), None);
+        assert_eq!(parse_device_time("2026-10-19 07:17:36"), None);
+        assert_eq!(parse_device_time(""), None);
+    }
+}
@@ -0,0 +1,138 @@
+use std::net::IpAddr;
+
+use serde::Serialize;
+
+use crate::core::Configuration;
+
+use super::process::run_adb;
+
+const PAIRING_SERVICE: &str = "_adb-tls-pairing._tcp";
+const CONNECT_SERVICE: &str = "_adb-tls-connect._tcp";
+const LEGACY_SERVICE:  &str = "_adb._tcp";
+
+#[derive(Serialize)]
+pub enum MdnsDiscoveryError {
+    UnrecognizedDebugBridgeOutput,
+    CannotRunProcess(String),
+    BadExitCode(Option<i32>),
+    DebugBridgePathMissing,
+    DiscoveryUnresponsive,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+pub enum MdnsServiceType {
+    Pairing,
+    Connect,
+    Legacy,
+}
+
+#[derive(Clone, Serialize)]
+pub struct MdnsService {
+    pub name:         String,
+    pub service_type: MdnsServiceType,
+    pub address:      IpAddr,
+    pub port:         u16,
+}
+
+// Lists the services adb discovered over mDNS: devices waiting to be paired, and paired devices accepting connections.
+pub async fn adb_mdns_services(configuration: &Configuration) -> Result<Vec<MdnsService>, MdnsDiscoveryError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(MdnsDiscoveryError::DebugBridgePathMissing)?;
+
+    let output = run_adb(adb_command, &["mdns", "services"], &configuration.timeouts.mdns_discovery).await?;
+
+    if !output.status.success() {
+        return Err(MdnsDiscoveryError::BadExitCode(output.status.code()));
+    }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    let mut lines = output.trim_end().lines();
+
+    if !lines.next().is_some_and(|header| header.starts_with("List of discovered mdns services")) {
+        return Err(MdnsDiscoveryError::UnrecognizedDebugBridgeOutput);
+    }
+
+    Ok(lines.filter_map(parse_service_line).collect())
+}
+
+// Recognizes transport ids such as `adb-R58M12345-AbCdEf._adb-tls-connect._tcp`, and returns the service instance name.
+pub fn parse_mdns_device_id(id: &str) -> Option<&str> {
+    let id = id.trim_end_matches('.');
+
+    [CONNECT_SERVICE, LEGACY_SERVICE]
+    .iter()
+    .find_map(|service| id.strip_suffix(service)?.strip_suffix('.'))
+}
+
+fn parse_service_line(line: &str) -> Option<MdnsService> {
+    let fields = line.split_whitespace().collect::<Vec<&str>>();
+
+    if fields.len() < 3 {
+        return None;
+    }
+
+    let service_type = match fields[1].trim_end_matches('.') {
+        PAIRING_SERVICE => MdnsServiceType::Pairing,
+        CONNECT_SERVICE => MdnsServiceType::Connect,
+        LEGACY_SERVICE  => MdnsServiceType::Legacy,
+
+        _ => return None,
+    };
+
+    let (address, port) = fields[2].rsplit_once(':')?;
+
+    Some(MdnsService {
+        service_type,
+
+        name:    fields[0].to_string(),
+        port:    port.parse().ok()?,
+        address: address.trim_start_matches('[').trim_end_matches(']').parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_mdns_device_id, parse_service_line, MdnsServiceType};
+
+    #[test]
+    fn parses_service_lines() {
+        // Captured with adb 35.0.2, which separates fields with tabs.
+        let service = parse_service_line("adb-R58M12ABCDE-AbCdEf\t_adb-tls-connect._tcp\t192.168.1.20:41235").unwrap();
+
+        assert_eq!(service.name, "adb-R58M12ABCDE-AbCdEf");
+        assert!(service.service_type == MdnsServiceType::Connect);
+        assert_eq!(service.address, "192.168.1.20".parse::<IpAddr>().unwrap());
+        assert_eq!(service.port, 41235);
+
+        let service = parse_service_line("adb-R58M12ABCDE-AbCdEf\t_adb-tls-pairing._tcp.\t[fe80::a1b2:c3ff:fed4:e5f6]:37123").unwrap();
+
+        assert!(service.service_type == MdnsServiceType::Pairing);
+        assert_eq!(service.address, "fe80::a1b2:c3ff:fed4:e5f6".parse::<IpAddr>().unwrap());
+        assert_eq!(service.port, 37123);
+
+        assert!(parse_service_line("adb-emulator-5554\t_adb._tcp\t10.0.2.15:5555").is_some_and(|service| service.service_type == MdnsServiceType::Legacy));
+    }
+
+    #[test]
+    fn skips_other_services_and_malformed_lines() {
+        assert!(parse_service_line("Chromecast-1a2b3c\t_googlecast._tcp\t192.168.1.40:8009").is_none());
+        assert!(parse_service_line("adb-R58M12ABCDE-AbCdEf\t_adb-tls-connect._tcp").is_none());
+        assert!(parse_service_line("adb-R58M12ABCDE-AbCdEf\t_adb-tls-connect._tcp\t192.168.1.20").is_none());
+    }
+
+    #[test]
+    fn recognizes_mdns_device_ids() {
+        assert_eq!(parse_mdns_device_id("adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp"), Some("adb-R58M12ABCDE-AbCdEf"));
+        assert_eq!(parse_mdns_device_id("adb-R58M12ABCDE-AbCdEf._adb-tls-connect._tcp."), Some("adb-R58M12ABCDE-AbCdEf"));
+        assert_eq!(parse_mdns_device_id("adb-emulator-5554._adb._tcp"), Some("adb-emulator-5554"));
+
+        // Pairing services cannot be connected to.
+        assert_eq!(parse_mdns_device_id("adb-R58M12ABCDE-AbCdEf._adb-tls-pairing._tcp"), None);
+        assert_eq!(parse_mdns_device_id("192.168.1.20:5555"), None);
+    }
+}
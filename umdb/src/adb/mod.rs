@@ -1,4 +1,24 @@
+pub mod ui;
+pub mod mdns;
+pub mod wait;
+pub mod flow;
 pub mod links;
+pub mod shell;
+pub mod input;
+pub mod power;
+pub mod logcat;
 pub mod device;
+pub mod target;
+pub mod pairing;
+pub mod settings;
+pub mod fan_out;
 pub mod connect;
+pub mod process;
+pub mod network;
+pub mod activity;
+pub mod baseline;
+pub mod overlays;
+pub mod demo_mode;
+pub mod permissions;
 pub mod executable;
+pub mod screenshot;
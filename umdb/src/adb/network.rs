@@ -0,0 +1,177 @@
+use std::{net::{IpAddr, Ipv6Addr}, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::{common::device::{DeviceListingError, NetworkAddress}, core::Configuration};
+
+use super::{process::run_adb, target::device_arguments};
+
+// Interfaces that cannot be reached from the host: mobile data, tunnels, Wi-Fi Direct and placeholders.
+const UNREACHABLE_INTERFACE_PREFIXES: [&str; 9] = ["rmnet", "v4-rmnet", "ccmni", "seth", "pdp", "tun", "p2p", "dummy", "ifb"];
+
+static IP_ADDRESS_REGEXP: LazyLock<Regex>       = LazyLock::new(|| Regex::new(r"^\d+:\s+([^\s@]+)\S*\s+inet6?\s+([0-9a-fA-F:.]+)/(\d+)").unwrap());
+static IFCONFIG_ADDRESS_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"inet6?\s+(?:addr:\s*)?([0-9a-fA-F:.]+)(?:/(\d+))?").unwrap());
+
+// Many recent builds no longer print `ifconfig` output in the old format, so `ip` is tried first.
+pub async fn find_network_addresses(configuration: &Configuration, adb_command: &str, device_id: &str) -> Result<Vec<NetworkAddress>, DeviceListingError> {
+    let output = run_adb(
+        adb_command,
+        &device_arguments(device_id, &["shell", "ip -o addr show 2>/dev/null || ifconfig"]),
+        &configuration.timeouts.network_discovery,
+    )
+    .await?;
+
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    let addresses = match output.lines().next().is_some_and(|line| line.starts_with(|character: char| character.is_ascii_digit())) {
+        true  => parse_ip_output(&output),
+        false => parse_ifconfig_output(&output),
+    };
+
+    Ok(
+        addresses
+        .into_iter()
+        .filter(|address| !address.address.is_loopback())
+        .collect()
+    )
+}
+
+pub fn network_address(interface: Option<&str>, address: IpAddr, prefix_length: Option<u8>) -> NetworkAddress {
+    let is_link_local = match address {
+        IpAddr::V4(address) => address.is_link_local(),
+        IpAddr::V6(address) => is_ipv6_link_local(&address),
+    };
+
+    let is_reachable_interface = interface.is_none_or(|interface| {
+        !UNREACHABLE_INTERFACE_PREFIXES.iter().any(|prefix| interface.starts_with(prefix))
+    });
+
+    NetworkAddress {
+        address,
+        prefix_length,
+        is_link_local,
+
+        // Link-local addresses would need a scope id that is only meaningful on the device.
+        is_connectable: is_reachable_interface && !is_link_local && !address.is_loopback(),
+        interface:      interface.map(str::to_string),
+    }
+}
+
+// Lines look like `30: wlan0    inet6 2001:db8::42/64 scope global dynamic`.
+fn parse_ip_output(output: &str) -> Vec<NetworkAddress> {
+    output
+    .lines()
+    .filter_map(|line| {
+        let captures = IP_ADDRESS_REGEXP.captures(line.trim())?;
+
+        let address = captures[2].parse::<IpAddr>().ok()?;
+
+        Some(network_address(Some(&captures[1]), address, captures[3].parse().ok()))
+    })
+    .collect()
+}
+
+// Interfaces start unindented blocks, in which addresses look like `inet addr:192.168.1.42` or `inet6 addr: fe80::1/64`.
+fn parse_ifconfig_output(output: &str) -> Vec<NetworkAddress> {
+    let mut interface = None;
+
+    output
+    .lines()
+    .filter_map(|line| {
+        if !line.starts_with(char::is_whitespace) {
+            interface = line.split_whitespace().next().map(str::to_string);
+        }
+
+        let captures = IFCONFIG_ADDRESS_REGEXP.captures(line)?;
+
+        let address = captures[1].parse::<IpAddr>().ok()?;
+
+        let prefix_length = captures.get(2).and_then(|length| length.as_str().parse().ok());
+
+        Some(network_address(interface.as_deref(), address, prefix_length))
+    })
+    .collect()
+}
+
+fn is_ipv6_link_local(address: &Ipv6Addr) -> bool {
+    address.segments()[0] & 0xffc0 == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_ifconfig_output, parse_ip_output};
+
+    // `ip -o addr show` on a Pixel 7 running Android 14, connected to Wi-Fi and mobile data.
+    const IP_OUTPUT: &str = "\
+1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever preferred_lft forever
+1: lo    inet6 ::1/128 scope host \\       valid_lft forever preferred_lft forever
+18: rmnet_data1    inet6 2a01:cb08:8a0:6e00:d6a3:a1ff:fe0b:12c4/64 scope global dynamic noprefixroute \\       valid_lft 3600sec preferred_lft 3600sec
+31: wlan0    inet 192.168.1.42/24 brd 192.168.1.255 scope global wlan0\\       valid_lft forever preferred_lft forever
+31: wlan0    inet6 2a01:e0a:1f2:b7c0:1c2d:3e4f:5a6b:7c8d/64 scope global dynamic mngtmpaddr noprefixroute \\       valid_lft 86182sec preferred_lft 86182sec
+31: wlan0    inet6 fe80::1c2d:3eff:fe4f:5a6b/64 scope link \\       valid_lft forever preferred_lft forever
+";
+
+    // Toybox `ifconfig` on an Android 7 device.
+    const IFCONFIG_OUTPUT: &str = "\
+wlan0     Link encap:Ethernet  HWaddr 3c:2e:ff:11:22:33  Driver icnss
+          inet addr:192.168.1.43  Bcast:192.168.1.255  Mask:255.255.255.0 
+          inet6 addr: fe80::3e2e:ffff:fe11:2233/64 Scope: Link
+          inet6 addr: 2a01:e0a:1f2:b7c0:3e2e:ffff:fe11:2233/64 Scope: Global
+          UP BROADCAST RUNNING MULTICAST  MTU:1500  Metric:1
+          RX packets:1204 errors:0 dropped:0 overruns:0 frame:0 
+
+lo        Link encap:Local Loopback 
+          inet addr:127.0.0.1  Mask:255.0.0.0 
+          UP LOOPBACK RUNNING  MTU:65536  Metric:1
+";
+
+    fn address(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_ip_output() {
+        let addresses = parse_ip_output(IP_OUTPUT);
+
+        assert_eq!(addresses.len(), 6);
+
+        let wlan0 = &addresses[3];
+
+        assert_eq!(wlan0.interface.as_deref(), Some("wlan0"));
+        assert_eq!(wlan0.address, address("192.168.1.42"));
+        assert_eq!(wlan0.prefix_length, Some(24));
+        assert!(wlan0.is_connectable);
+
+        let global_ipv6 = &addresses[4];
+
+        assert_eq!(global_ipv6.address, address("2a01:e0a:1f2:b7c0:1c2d:3e4f:5a6b:7c8d"));
+        assert!(global_ipv6.is_connectable && !global_ipv6.is_link_local);
+
+        let link_local_ipv6 = &addresses[5];
+
+        assert!(link_local_ipv6.is_link_local && !link_local_ipv6.is_connectable);
+
+        // Mobile data addresses cannot be reached from the host.
+        assert_eq!(addresses[2].interface.as_deref(), Some("rmnet_data1"));
+        assert!(!addresses[2].is_connectable);
+    }
+
+    #[test]
+    fn parses_ifconfig_output() {
+        let addresses = parse_ifconfig_output(IFCONFIG_OUTPUT);
+
+        let summary = addresses
+        .iter()
+        .map(|address| (address.interface.as_deref().unwrap(), address.address, address.prefix_length))
+        .collect::<Vec<_>>();
+
+        assert_eq!(summary, [
+            ("wlan0", address("192.168.1.43"), None),
+            ("wlan0", address("fe80::3e2e:ffff:fe11:2233"), Some(64)),
+            ("wlan0", address("2a01:e0a:1f2:b7c0:3e2e:ffff:fe11:2233"), Some(64)),
+            ("lo", address("127.0.0.1"), None),
+        ]);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::Configuration;
+
+use super::shell::{run_shell, ShellError};
+
+// `SYSPROPS_TRANSACTION`: makes running apps read system properties again, so that they draw overlays without
+// being restarted.
+const POKE_SYSTEM_PROPERTIES_COMMAND: &str = "service call activity 1599295570";
+
+#[derive(Serialize)]
+pub enum OverlayError {
+    Shell(ShellError),
+}
+
+// The developer options drawing over apps.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overlay {
+    ShowTaps,
+    PointerLocation,
+    LayoutBounds,
+    GpuOverdraw,
+    ProfileGpuRendering,
+    StrictModeFlashes,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 6] = [
+        Overlay::ShowTaps,
+        Overlay::PointerLocation,
+        Overlay::LayoutBounds,
+        Overlay::GpuOverdraw,
+        Overlay::ProfileGpuRendering,
+        Overlay::StrictModeFlashes,
+    ];
+
+    fn read_command(&self) -> &'static str {
+        match self {
+            Overlay::ShowTaps            => "settings get system show_touches",
+            Overlay::PointerLocation     => "settings get system pointer_location",
+            Overlay::LayoutBounds        => "getprop debug.layout",
+            Overlay::GpuOverdraw         => "getprop debug.hwui.overdraw",
+            Overlay::ProfileGpuRendering => "getprop debug.hwui.profile",
+            Overlay::StrictModeFlashes   => "getprop persist.sys.strictmode.visual",
+        }
+    }
+
+    fn is_enabled(&self, value: &str) -> bool {
+        match self {
+            Overlay::ShowTaps | Overlay::PointerLocation => value == "1",
+
+            // Overdraw can also be shown for deuteranomaly, and rendering profiled as lines instead of bars.
+            Overlay::GpuOverdraw         => value.starts_with("show"),
+            Overlay::ProfileGpuRendering => value.starts_with("visual_"),
+
+            Overlay::LayoutBounds | Overlay::StrictModeFlashes => value == "true" || value == "1",
+        }
+    }
+
+    fn write_command(&self, enabled: bool) -> String {
+        let (command, value) = match self {
+            Overlay::ShowTaps            => ("settings put system show_touches", if enabled { "1" } else { "0" }),
+            Overlay::PointerLocation     => ("settings put system pointer_location", if enabled { "1" } else { "0" }),
+            Overlay::LayoutBounds        => ("setprop debug.layout", if enabled { "true" } else { "false" }),
+            Overlay::GpuOverdraw         => ("setprop debug.hwui.overdraw", if enabled { "show" } else { "false" }),
+            Overlay::ProfileGpuRendering => ("setprop debug.hwui.profile", if enabled { "visual_bars" } else { "false" }),
+            Overlay::StrictModeFlashes   => ("setprop persist.sys.strictmode.visual", if enabled { "1" } else { "0" }),
+        };
+
+        match self {
+            Overlay::ShowTaps | Overlay::PointerLocation => format!("{command} {value}"),
+
+            _ => format!("{command} {value} && {POKE_SYSTEM_PROPERTIES_COMMAND} > /dev/null"),
+        }
+    }
+}
+
+pub async fn adb_get_overlays(configuration: &Configuration, device_id: &str) -> Result<BTreeMap<Overlay, bool>, OverlayError> {
+    // One line per overlay. Properties that were never set are printed as empty lines.
+    let command = Overlay::ALL
+    .map(|overlay| overlay.read_command())
+    .join("; ");
+
+    let output = run_shell(configuration, device_id, &command)
+    .await
+    .map_err(OverlayError::Shell)?;
+
+    Ok(parse_overlays(&output))
+}
+
+// Returns whether each overlay is enabled once the change is made.
+pub async fn adb_set_overlay(configuration: &Configuration, device_id: &str, overlay: Overlay, enabled: bool) -> Result<BTreeMap<Overlay, bool>, OverlayError> {
+    run_shell(configuration, device_id, &overlay.write_command(enabled))
+    .await
+    .map_err(OverlayError::Shell)?;
+
+    adb_get_overlays(configuration, device_id).await
+}
+
+fn parse_overlays(output: &str) -> BTreeMap<Overlay, bool> {
+    let mut values = output.lines().map(str::trim);
+
+    Overlay::ALL
+    .into_iter()
+    .map(|overlay| (overlay, overlay.is_enabled(values.next().unwrap_or_default())))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_overlays, Overlay};
+
+    // The read commands on a Pixel 7 running Android 14, with taps, layout bounds and overdraw shown. The pointer
+    // location was never set, and GPU rendering is profiled as lines.
+    const OVERLAYS_OUTPUT: &str = "1\nnull\ntrue\nshow\nvisual_lines\n\n";
+
+    #[test]
+    fn parses_overlays() {
+        let overlays = parse_overlays(OVERLAYS_OUTPUT);
+
+        assert_eq!(overlays.len(), Overlay::ALL.len());
+
+        assert!(overlays[&Overlay::ShowTaps]);
+        assert!(!overlays[&Overlay::PointerLocation]);
+        assert!(overlays[&Overlay::LayoutBounds]);
+        assert!(overlays[&Overlay::GpuOverdraw]);
+        assert!(overlays[&Overlay::ProfileGpuRendering]);
+        assert!(!overlays[&Overlay::StrictModeFlashes]);
+    }
+
+    #[test]
+    fn parses_missing_overlays_as_disabled() {
+        assert!(parse_overlays("").values().all(|enabled| !enabled));
+        assert!(!parse_overlays("0\n0\nfalse\nfalse\nfalse\n0\n").values().any(|enabled| *enabled));
+    }
+
+    #[test]
+    fn pokes_properties_of_drawn_overlays() {
+        assert_eq!(Overlay::ShowTaps.write_command(true), "settings put system show_touches 1");
+        assert_eq!(Overlay::GpuOverdraw.write_command(false), "setprop debug.hwui.overdraw false && service call activity 1599295570 > /dev/null");
+    }
+}
@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+use crate::core::Configuration;
+
+use super::{mdns::{adb_mdns_services, MdnsDiscoveryError, MdnsServiceType}, process::run_adb};
+
+#[derive(Serialize)]
+pub enum PairingError {
+    DiscoveryFailed(MdnsDiscoveryError),
+    CannotRunProcess(String),
+    PairingServiceNotFound,
+    DebugBridgePathMissing,
+    DeviceUnresponsive,
+    PairingFailed(String),
+}
+
+#[derive(Serialize)]
+pub struct PairedDevice {
+    pub address: String,
+    pub guid:    Option<String>,
+}
+
+// Pairs with a device showing the "Pair device with pairing code" dialog of Android 11+ wireless debugging.
+pub async fn adb_pair(configuration: &Configuration, host: &str, port: u16, code: &str) -> Result<PairedDevice, PairingError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(PairingError::DebugBridgePathMissing)?;
+
+    let address = match host.contains(':') {
+        true  => format!("[{host}]:{port}"),
+        false => format!("{host}:{port}"),
+    };
+
+    let output = run_adb(adb_command, &["pair", &address, code], &configuration.timeouts.pairing).await?;
+
+    // adb reports some failures with a zero exit code, so the output is the only reliable signal.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let Some(guid) = parse_pairing_output(&stdout) else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        return Err(PairingError::PairingFailed(format!("{}\n{}", stdout.trim(), stderr.trim()).trim().to_string()));
+    };
+
+    Ok(PairedDevice { address, guid })
+}
+
+// `None` when pairing failed, otherwise the GUID of the device, when adb printed it.
+fn parse_pairing_output(stdout: &str) -> Option<Option<String>> {
+    let success_line = stdout
+    .lines()
+    .find(|line| line.starts_with("Successfully paired to "))?;
+
+    let guid = success_line
+    .trim_end()
+    .split_once("[guid=")
+    .and_then(|(_, guid)| guid.strip_suffix(']'))
+    .map(str::to_string);
+
+    Some(guid)
+}
+
+// Pairs with a device that scanned a QR code made from `adb_pairing_qr_payload`. The device then advertises a
+// pairing service named after the QR code, which tells us where to send the password.
+pub async fn adb_pair_with_qr_code(configuration: &Configuration, service_name: &str, password: &str) -> Result<PairedDevice, PairingError> {
+    let service = adb_mdns_services(configuration)
+    .await
+    .map_err(PairingError::DiscoveryFailed)?
+    .into_iter()
+    .find(|service| service.service_type == MdnsServiceType::Pairing && service.name == service_name)
+    .ok_or(PairingError::PairingServiceNotFound)?;
+
+    adb_pair(configuration, &service.address.to_string(), service.port, password).await
+}
+
+// Payload of the QR codes scanned from the "Pair device with QR code" screen of Android 11+ wireless debugging.
+pub fn adb_pairing_qr_payload(service_name: &str, password: &str) -> String {
+    format!("WIFI:T:ADB;S:{};P:{};;", escape_qr_field(service_name), escape_qr_field(password))
+}
+
+fn escape_qr_field(value: &str) -> String {
+    value
+    .chars()
+    .flat_map(|character| match character {
+        '\\' | ';' | ',' | ':' | '"' => vec!['\\', character],
+        _                            => vec![character],
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adb_pairing_qr_payload, escape_qr_field, parse_pairing_output};
+
+    #[test]
+    fn parses_pairing_outputs() {
+        // Printed by adb 35.0.2.
+        assert_eq!(parse_pairing_output("Successfully paired to 192.168.1.20:37123 [guid=adb-R58M12ABCDE-AbCdEf]\n"), Some(Some("adb-R58M12ABCDE-AbCdEf".to_string())));
+        assert_eq!(parse_pairing_output("Successfully paired to 192.168.1.20:37123\n"), Some(None));
+        assert_eq!(parse_pairing_output("Failed: Wrong password or connection was dropped.\n"), None);
+    }
+
+    #[test]
+    fn builds_pairing_qr_payloads() {
+        assert_eq!(adb_pairing_qr_payload("studio-Xy3fG1", "482913"), "WIFI:T:ADB;S:studio-Xy3fG1;P:482913;;");
+
+        // Characters with a meaning in the payload are escaped with backslashes.
+        assert_eq!(adb_pairing_qr_payload("studio;lab:1", r#"p\a,s"s"#), r#"WIFI:T:ADB;S:studio\;lab\:1;P:p\\a\,s\"s;;"#);
+        assert_eq!(escape_qr_field(r"a\b"), r"a\\b");
+    }
+}
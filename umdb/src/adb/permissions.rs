@@ -0,0 +1,375 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::Configuration;
+
+use super::shell::{quote, run_shell, ShellError};
+
+static NAME_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_.]+$").unwrap());
+
+#[derive(Serialize)]
+pub enum PermissionError {
+    Shell(ShellError),
+    PackageNotFound(String),
+    InvalidName(String),
+}
+
+#[derive(Serialize)]
+pub struct DeclaredPermission {
+    pub name: String,
+
+    // `normal`, `dangerous`, `signature`…
+    pub protection: Option<String>,
+}
+
+// Flags are lowercase versions of those Android prints, such as `user_fixed` or `review_required`.
+#[derive(Serialize)]
+pub struct PermissionState {
+    pub name: String,
+    pub granted: bool,
+    pub flags: Vec<String>,
+}
+
+// Runtime permissions are those of the first user of the device, usually the main one.
+#[derive(Default, Serialize)]
+pub struct PackagePermissions {
+    pub declared: Vec<DeclaredPermission>,
+    pub requested: Vec<String>,
+    pub install: Vec<PermissionState>,
+    pub runtime: Vec<PermissionState>,
+
+    // Operation, such as `SYSTEM_ALERT_WINDOW`, then mode.
+    pub app_ops: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppOpMode {
+    Allow,
+    Ignore,
+    Deny,
+    Default,
+    Foreground,
+}
+
+// Special access, such as drawing over other apps, is not granted through permissions but through app operations.
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PermissionChange {
+    Grant {
+        permission: String,
+    },
+
+    Revoke {
+        permission: String,
+    },
+
+    SetAppOp {
+        op: String,
+        mode: AppOpMode,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PermissionSection {
+    Declared,
+    Requested,
+    Install,
+    Runtime,
+}
+
+pub async fn adb_package_permissions(configuration: &Configuration, device_id: &str, package: &str) -> Result<PackagePermissions, PermissionError> {
+    check_name(package)?;
+
+    let package_dump_command = format!("dumpsys package {}", quote(package));
+    let app_ops_command      = format!("appops get {}", quote(package));
+
+    let (package_dump, app_ops) = futures::join!(
+        run_shell(configuration, device_id, &package_dump_command),
+        run_shell(configuration, device_id, &app_ops_command),
+    );
+
+    let mut permissions = parse_package_dump(&package_dump.map_err(PermissionError::Shell)?, package)?;
+
+    permissions.app_ops = parse_app_ops(&app_ops.map_err(PermissionError::Shell)?);
+
+    Ok(permissions)
+}
+
+// Returns the permissions of the package once changed.
+pub async fn adb_change_permission(configuration: &Configuration, device_id: &str, package: &str, change: &PermissionChange) -> Result<PackagePermissions, PermissionError> {
+    check_name(package)?;
+
+    let command = match change {
+        PermissionChange::Grant { permission }  => format!("pm grant {} {}", quote(package), quote(check_name(permission)?)),
+        PermissionChange::Revoke { permission } => format!("pm revoke {} {}", quote(package), quote(check_name(permission)?)),
+
+        PermissionChange::SetAppOp { op, mode } => format!("appops set {} {} {}", quote(package), quote(check_name(op)?), match mode {
+            AppOpMode::Allow      => "allow",
+            AppOpMode::Ignore     => "ignore",
+            AppOpMode::Deny       => "deny",
+            AppOpMode::Default    => "default",
+            AppOpMode::Foreground => "foreground",
+        }),
+    };
+
+    run_permission_command(configuration, device_id, &command).await?;
+
+    adb_package_permissions(configuration, device_id, package).await
+}
+
+// Android can only reset the runtime permissions of all apps at once.
+pub async fn adb_reset_permissions(configuration: &Configuration, device_id: &str) -> Result<(), PermissionError> {
+    run_permission_command(configuration, device_id, "pm reset-permissions").await
+}
+
+async fn run_permission_command(configuration: &Configuration, device_id: &str, command: &str) -> Result<(), PermissionError> {
+    let output = run_shell(configuration, device_id, command)
+    .await
+    .map_err(PermissionError::Shell)?;
+
+    // Older versions of Android report errors with a zero exit code.
+    match output.contains("Exception") || output.contains("Error") {
+        true  => Err(PermissionError::Shell(ShellError::CommandFailed(output.trim().to_string()))),
+        false => Ok(()),
+    }
+}
+
+// Permissions are listed under the block of the package:
+//
+//   Package [com.example] (1a2b3c):
+//     declared permissions:
+//       com.example.permission.C2D_MESSAGE: prot=signature, INSTALLED
+//     requested permissions:
+//       android.permission.CAMERA
+//     install permissions:
+//       android.permission.INTERNET: granted=true
+//     User 0: ceDataInode=4242 installed=true hidden=false
+//       runtime permissions:
+//         android.permission.CAMERA: granted=false, flags=[ USER_SET|USER_FIXED ]
+fn parse_package_dump(output: &str, package: &str) -> Result<PackagePermissions, PermissionError> {
+    let header = format!("Package [{package}]");
+    let indent = |line: &str| line.len() - line.trim_start().len();
+
+    let mut lines = output.lines();
+
+    let package_indent = lines
+    .by_ref()
+    .find(|line| line.trim_start().starts_with(&header))
+    .map(indent)
+    .ok_or_else(|| PermissionError::PackageNotFound(package.to_string()))?;
+
+    let mut permissions    = PackagePermissions::default();
+    let mut section        = None::<(PermissionSection, usize)>;
+    let mut runtime_parsed = false;
+
+    for line in lines {
+        let line_indent = indent(line);
+        let line        = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line_indent <= package_indent {
+            break;
+        }
+
+        if let Some((current_section, section_indent)) = section {
+            if line_indent > section_indent {
+                add_permission(&mut permissions, current_section, line);
+
+                continue;
+            }
+
+            if current_section == PermissionSection::Runtime {
+                runtime_parsed = true;
+            }
+        }
+
+        let new_section = match line {
+            "declared permissions:"                   => Some(PermissionSection::Declared),
+            "requested permissions:"                  => Some(PermissionSection::Requested),
+            "install permissions:"                    => Some(PermissionSection::Install),
+            "runtime permissions:" if !runtime_parsed => Some(PermissionSection::Runtime),
+            _                                         => None,
+        };
+
+        section = new_section.map(|new_section| (new_section, line_indent));
+    }
+
+    Ok(permissions)
+}
+
+fn add_permission(permissions: &mut PackagePermissions, section: PermissionSection, line: &str) {
+    let (name, details) = line.split_once(':').unwrap_or((line, ""));
+
+    let name = name.trim().to_string();
+
+    match section {
+        PermissionSection::Requested => permissions.requested.push(name),
+
+        PermissionSection::Declared => {
+            let protection = details
+            .split(',')
+            .find_map(|detail| detail.trim().strip_prefix("prot="))
+            .map(str::to_string);
+
+            permissions.declared.push(DeclaredPermission { name, protection });
+        },
+
+        PermissionSection::Install | PermissionSection::Runtime => {
+            let flags = details
+            .split_once("flags=[")
+            .and_then(|(_, flags)| flags.split_once(']'))
+            .map(|(flags, _)| {
+                flags
+                .split('|')
+                .map(|flag| flag.trim().to_lowercase())
+                .filter(|flag| !flag.is_empty())
+                .collect()
+            })
+            .unwrap_or_default();
+
+            let state = PermissionState { granted: details.contains("granted=true"), name, flags };
+
+            match section {
+                PermissionSection::Install => permissions.install.push(state),
+                _                          => permissions.runtime.push(state),
+            }
+        },
+    }
+}
+
+// Prints `SYSTEM_ALERT_WINDOW: allow; time=+2h ago`, with a `Uid mode: ` prefix for modes set on the whole app.
+fn parse_app_ops(output: &str) -> BTreeMap<String, String> {
+    output
+    .lines()
+    .map(|line| line.trim().trim_start_matches("Uid mode: "))
+    .filter_map(|line| line.split_once(": "))
+    .filter(|(op, _)| !op.is_empty() && op.chars().all(|character| character.is_ascii_uppercase() || character.is_ascii_digit() || character == '_'))
+    .map(|(op, mode)| (op.to_string(), mode.split(';').next().unwrap_or_default().trim().to_string()))
+    .collect()
+}
+
+fn check_name(name: &str) -> Result<&str, PermissionError> {
+    match NAME_REGEXP.is_match(name) {
+        true  => Ok(name),
+        false => Err(PermissionError::InvalidName(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_name, parse_app_ops, parse_package_dump, PermissionError};
+
+    // `dumpsys package com.example` on a Pixel 7 running Android 14, with a second user. Trimmed.
+    const PACKAGE_DUMP: &str = "\
+Activity Resolver Table:
+  Non-Data Actions:
+      android.intent.action.MAIN:
+        5e6f7a8 com.example/.MainActivity filter 9b8c7d6
+
+Permissions:
+  Permission [com.example.permission.C2D_MESSAGE] (3c4d5e6):
+    sourcePackage=com.example
+    perm=PermissionInfo{1a2b3c4 com.example.permission.C2D_MESSAGE}
+
+Packages:
+  Package [com.example] (7f8e9d0):
+    userId=10123
+    pkg=Package{2b3c4d5 com.example}
+    versionName=4.2.0
+    declared permissions:
+      com.example.permission.C2D_MESSAGE: prot=signature, INSTALLED
+      com.example.permission.SYNC: prot=normal|appop, INSTALLED
+    requested permissions:
+      android.permission.INTERNET
+      android.permission.CAMERA
+      android.permission.POST_NOTIFICATIONS
+      android.permission.SYSTEM_ALERT_WINDOW
+    install permissions:
+      android.permission.INTERNET: granted=true
+      com.example.permission.C2D_MESSAGE: granted=true
+    User 0: ceDataInode=4242 installed=true hidden=false suspended=false distractionFlags=0 stopped=false notLaunched=false enabled=0 instant=false virtual=false
+      gids=[3003]
+      runtime permissions:
+        android.permission.POST_NOTIFICATIONS: granted=false, flags=[ USER_SENSITIVE_WHEN_GRANTED|USER_SENSITIVE_WHEN_DENIED|USER_SET]
+        android.permission.CAMERA: granted=true, flags=[ USER_SET|USER_SENSITIVE_WHEN_GRANTED|USER_SENSITIVE_WHEN_DENIED]
+      enabledComponents:
+        com.example.SyncService
+    User 10: ceDataInode=0 installed=true hidden=false suspended=false distractionFlags=0 stopped=true notLaunched=true enabled=0 instant=false virtual=false
+      runtime permissions:
+        android.permission.CAMERA: granted=false, flags=[ REVIEW_REQUIRED ]
+
+Queries:
+  system apps queryable: false
+";
+
+    // `appops get com.example` on the same device.
+    const APP_OPS_OUTPUT: &str = "\
+Uid mode: SYSTEM_ALERT_WINDOW: allow
+CAMERA: allow; time=+2h14m3s120ms ago; duration=+3s10ms
+POST_NOTIFICATION: ignore; rejectTime=+1d2h ago
+WAKE_LOCK: allow; time=+5m ago
+";
+
+    #[test]
+    fn parses_package_dump() {
+        let permissions = parse_package_dump(PACKAGE_DUMP, "com.example").ok().unwrap();
+
+        let declared = permissions.declared.iter().map(|permission| (permission.name.as_str(), permission.protection.as_deref())).collect::<Vec<_>>();
+
+        assert_eq!(declared, [("com.example.permission.C2D_MESSAGE", Some("signature")), ("com.example.permission.SYNC", Some("normal|appop"))]);
+
+        assert_eq!(permissions.requested.len(), 4);
+        assert_eq!(permissions.requested[3], "android.permission.SYSTEM_ALERT_WINDOW");
+
+        assert_eq!(permissions.install.len(), 2);
+        assert!(permissions.install.iter().all(|permission| permission.granted && permission.flags.is_empty()));
+
+        // Only those of the first user.
+        assert_eq!(permissions.runtime.len(), 2);
+
+        let notifications = &permissions.runtime[0];
+
+        assert_eq!(notifications.name, "android.permission.POST_NOTIFICATIONS");
+        assert!(!notifications.granted);
+        assert_eq!(notifications.flags, ["user_sensitive_when_granted", "user_sensitive_when_denied", "user_set"]);
+
+        let camera = &permissions.runtime[1];
+
+        assert!(camera.granted);
+        assert!(camera.flags.contains(&"user_set".to_string()));
+    }
+
+    #[test]
+    fn reports_missing_packages() {
+        assert!(matches!(parse_package_dump(PACKAGE_DUMP, "com.example.debug"), Err(PermissionError::PackageNotFound(package)) if package == "com.example.debug"));
+        assert!(matches!(parse_package_dump("", "com.example"), Err(PermissionError::PackageNotFound(_))));
+    }
+
+    #[test]
+    fn parses_app_ops() {
+        let app_ops = parse_app_ops(APP_OPS_OUTPUT);
+
+        assert_eq!(app_ops.len(), 4);
+        assert_eq!(app_ops["SYSTEM_ALERT_WINDOW"], "allow");
+        assert_eq!(app_ops["CAMERA"], "allow");
+        assert_eq!(app_ops["POST_NOTIFICATION"], "ignore");
+
+        assert!(parse_app_ops("No operations.\n").is_empty());
+    }
+
+    #[test]
+    fn checks_names() {
+        assert!(check_name("android.permission.CAMERA").is_ok());
+        assert!(check_name("SYSTEM_ALERT_WINDOW").is_ok());
+
+        assert!(check_name("").is_err());
+        assert!(check_name("com.example; reboot").is_err());
+        assert!(check_name("com.example'").is_err());
+    }
+}
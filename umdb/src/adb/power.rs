@@ -0,0 +1,208 @@
+use std::{collections::BTreeSet, time::{Duration, Instant}};
+
+use serde::Serialize;
+
+use crate::{common::ui::{Bounds, UiActionError, UiActionRequest, UiDumpError, UiNode, UiSelector}, core::{Configuration, UnlockCredential, UnlockCredentialsError}};
+
+use super::{activity::{adb_activity_state, ActivityError}, shell::{quote, run_shell, ShellError}, ui::adb_ui_action};
+
+// Views of the keyguard of SystemUI.
+const PIN_ENTRY_ID: &str    = "pinEntry";
+const PATTERN_VIEW_ID: &str = "lockPatternView";
+
+const KEYGUARD_POLLING_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Serialize)]
+pub enum PowerError {
+    Shell(ShellError),
+    Ui(UiDumpError),
+    Activity(ActivityError),
+    Credentials(UnlockCredentialsError),
+    CredentialMissing,
+    InvalidCredential,
+    CredentialEntryNotFound,
+    StillLocked,
+    NoKeepAwakeSession,
+}
+
+pub async fn adb_wake(configuration: &Configuration, device_id: &str) -> Result<(), PowerError> {
+    run(configuration, device_id, "input keyevent KEYCODE_WAKEUP").await
+}
+
+// Unlocks devices without a secure lock screen. Devices with one show their PIN or pattern entry instead.
+pub async fn adb_dismiss_keyguard(configuration: &Configuration, device_id: &str) -> Result<(), PowerError> {
+    run(configuration, device_id, "input keyevent KEYCODE_WAKEUP && wm dismiss-keyguard").await
+}
+
+// Enters the credential of the device from the configuration once the keyguard shows its entry, then waits for the
+// keyguard to go away, so that deep links opened afterwards are actually seen.
+pub async fn adb_unlock(configuration: &Configuration, device_id: &str) -> Result<(), PowerError> {
+    let credential = configuration
+    .find_unlock_credential(device_id)
+    .map_err(PowerError::Credentials)?
+    .ok_or(PowerError::CredentialMissing)?;
+
+    check_credential(&credential)?;
+
+    adb_dismiss_keyguard(configuration, device_id).await?;
+
+    let command = match &credential {
+        UnlockCredential::Pin(pin) => {
+            wait_for_credential_entry(configuration, device_id, PIN_ENTRY_ID).await?;
+
+            format!("input text {} && input keyevent KEYCODE_ENTER", quote(pin))
+        },
+
+        UnlockCredential::Pattern(dots) => {
+            let pattern_view = wait_for_credential_entry(configuration, device_id, PATTERN_VIEW_ID).await?;
+
+            pattern_command(pattern_view.bounds.ok_or(PowerError::CredentialEntryNotFound)?, dots)
+        },
+    };
+
+    run(configuration, device_id, &command).await?;
+
+    wait_for_keyguard_dismissal(configuration, device_id).await
+}
+
+// Keeps the screen on while the device is charging. Returns the previous value of the setting.
+pub async fn adb_keep_awake(configuration: &Configuration, device_id: &str) -> Result<u32, PowerError> {
+    let output = run_shell(configuration, device_id, "settings get global stay_on_while_plugged_in")
+    .await
+    .map_err(PowerError::Shell)?;
+
+    // `null` when never set.
+    let previous_value = output.trim().parse().unwrap_or(0);
+
+    run(configuration, device_id, "svc power stayon true").await?;
+
+    Ok(previous_value)
+}
+
+pub async fn adb_restore_stay_on(configuration: &Configuration, device_id: &str, value: u32) -> Result<(), PowerError> {
+    run(configuration, device_id, &format!("settings put global stay_on_while_plugged_in {value}")).await
+}
+
+// PINs are typed as text. Patterns join at least 4 distinct dots.
+fn check_credential(credential: &UnlockCredential) -> Result<(), PowerError> {
+    let valid = match credential {
+        UnlockCredential::Pin(pin) => !pin.is_empty() && pin.chars().all(|character| character.is_ascii_digit()),
+
+        UnlockCredential::Pattern(dots) => {
+            let distinct_dots = dots.iter().collect::<BTreeSet<_>>();
+
+            dots.len() >= 4 && distinct_dots.len() == dots.len() && dots.iter().all(|dot| (1..=9).contains(dot))
+        },
+    };
+
+    match valid {
+        true  => Ok(()),
+        false => Err(PowerError::InvalidCredential),
+    }
+}
+
+async fn wait_for_credential_entry(configuration: &Configuration, device_id: &str, resource_id: &str) -> Result<UiNode, PowerError> {
+    let request = UiActionRequest {
+        selector:   UiSelector { resource_id: Some(resource_id.to_string()), ..UiSelector::default() },
+        action:     None,
+        timeout_ms: Some(configuration.timeouts.shell.timeout_ms),
+    };
+
+    adb_ui_action(configuration, device_id, &request)
+    .await
+    .map_err(|error| match error {
+        UiActionError::Dump(error) => PowerError::Ui(error),
+        _                          => PowerError::CredentialEntryNotFound,
+    })
+}
+
+async fn wait_for_keyguard_dismissal(configuration: &Configuration, device_id: &str) -> Result<(), PowerError> {
+    let started_at = Instant::now();
+
+    loop {
+        let state = adb_activity_state(configuration, device_id)
+        .await
+        .map_err(PowerError::Activity)?;
+
+        if state.keyguard_showing != Some(true) {
+            return Ok(());
+        }
+
+        if started_at.elapsed() >= configuration.timeouts.shell.timeout() {
+            return Err(PowerError::StillLocked);
+        }
+
+        tokio::time::sleep(KEYGUARD_POLLING_INTERVAL).await;
+    }
+}
+
+// Android 11 and later can draw patterns through `input motionevent`.
+fn pattern_command(bounds: Bounds, dots: &[u8]) -> String {
+    // Dots are at the center of the cells of a 3 by 3 grid covering the view.
+    let positions = dots
+    .iter()
+    .map(|dot| {
+        let column = ((dot - 1) % 3) as i32;
+        let row    = ((dot - 1) / 3) as i32;
+
+        let x = bounds.left + (bounds.right - bounds.left) * (2 * column + 1) / 6;
+        let y = bounds.top + (bounds.bottom - bounds.top) * (2 * row + 1) / 6;
+
+        (x, y)
+    })
+    .collect::<Vec<_>>();
+
+    let last_position = positions[positions.len() - 1];
+
+    let commands = positions
+    .iter()
+    .enumerate()
+    .map(|(index, (x, y))| match index {
+        0 => format!("input motionevent DOWN {x} {y}"),
+        _ => format!("input motionevent MOVE {x} {y}"),
+    })
+    .chain([format!("input motionevent UP {} {}", last_position.0, last_position.1)])
+    .collect::<Vec<_>>();
+
+    commands.join(" && ")
+}
+
+async fn run(configuration: &Configuration, device_id: &str, command: &str) -> Result<(), PowerError> {
+    run_shell(configuration, device_id, command)
+    .await
+    .map_err(PowerError::Shell)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::ui::Bounds, core::UnlockCredential};
+
+    use super::{check_credential, pattern_command};
+
+    #[test]
+    fn checks_credentials() {
+        assert!(check_credential(&UnlockCredential::Pin("0420".to_string())).is_ok());
+        assert!(check_credential(&UnlockCredential::Pattern(vec![1, 2, 3, 6, 9])).is_ok());
+
+        assert!(check_credential(&UnlockCredential::Pin(String::new())).is_err());
+        assert!(check_credential(&UnlockCredential::Pin("12 34".to_string())).is_err());
+        assert!(check_credential(&UnlockCredential::Pattern(vec![1, 2, 3])).is_err());
+        assert!(check_credential(&UnlockCredential::Pattern(vec![1, 2, 3, 2])).is_err());
+        assert!(check_credential(&UnlockCredential::Pattern(vec![0, 1, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn draws_patterns_through_cell_centers() {
+        // The pattern view of a Pixel 7 in portrait.
+        let bounds = Bounds { left: 90, top: 1287, right: 990, bottom: 2187 };
+
+        assert_eq!(pattern_command(bounds, &[1, 5, 9, 6]), "\
+input motionevent DOWN 240 1437 && \
+input motionevent MOVE 540 1737 && \
+input motionevent MOVE 840 2037 && \
+input motionevent MOVE 840 1737 && \
+input motionevent UP 840 1737");
+    }
+}
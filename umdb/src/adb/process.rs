@@ -0,0 +1,106 @@
+use std::{ffi::OsStr, process::Output};
+
+use tokio::{process::Command, time::timeout};
+
+use crate::{common::{device::DeviceListingError, input::InputError, links::OpenDeepLinkError, ui::UiDumpError}, core::OperationPolicy};
+
+use super::{connect::AdbConnectError, mdns::MdnsDiscoveryError, pairing::PairingError, shell::ShellError};
+
+pub enum AdbProcessError {
+    CannotRunProcess(String),
+    DeviceUnresponsive,
+}
+
+// Runs adb, killing it when it exceeds the timeout of the policy. Timed out runs are retried as many times as the policy
+// allows. Processes are also killed when the returned future is dropped, which happens when an HTTP client disconnects.
+pub async fn run_adb<Argument: AsRef<OsStr>>(adb_command: &str, arguments: &[Argument], policy: &OperationPolicy) -> Result<Output, AdbProcessError> {
+    let mut attempts_left = policy.retries;
+
+    loop {
+        let process_task = Command
+        ::new(adb_command)
+        .args(arguments)
+        .kill_on_drop(true)
+        .output();
+
+        match timeout(policy.timeout(), process_task).await {
+            Ok(Ok(output)) => return Ok(output),
+            Ok(Err(error)) => return Err(AdbProcessError::CannotRunProcess(error.to_string())),
+
+            Err(_) if attempts_left > 0 => attempts_left -= 1,
+            Err(_)                      => return Err(AdbProcessError::DeviceUnresponsive),
+        }
+    }
+}
+
+impl From<AdbProcessError> for DeviceListingError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => DeviceListingError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => DeviceListingError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for OpenDeepLinkError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => OpenDeepLinkError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => OpenDeepLinkError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for AdbConnectError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => AdbConnectError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => AdbConnectError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for MdnsDiscoveryError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => MdnsDiscoveryError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => MdnsDiscoveryError::DiscoveryUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for PairingError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => PairingError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => PairingError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for InputError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => InputError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => InputError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for UiDumpError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => UiDumpError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => UiDumpError::DeviceUnresponsive,
+        }
+    }
+}
+
+impl From<AdbProcessError> for ShellError {
+    fn from(error: AdbProcessError) -> Self {
+        match error {
+            AdbProcessError::CannotRunProcess(message) => ShellError::CannotRunProcess(message),
+            AdbProcessError::DeviceUnresponsive        => ShellError::DeviceUnresponsive,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use crate::core::Configuration;
+
+use super::{demo_mode::{adb_enter_demo_mode, adb_exit_demo_mode, adb_restore_demo_allowed, DemoModeError, DemoModeOptions}, shell::{run_exec_out, ShellError}};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+// Time SystemUI takes to redraw the status bar once in demo mode.
+const DEMO_MODE_SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+pub async fn adb_screenshot(configuration: &Configuration, device_id: &str) -> Result<Vec<u8>, ShellError> {
+    let image = run_exec_out(configuration, device_id, "screencap -p").await?;
+
+    // screencap prints its errors on the standard output, with a zero exit code.
+    match image.starts_with(PNG_SIGNATURE) {
+        true  => Ok(image),
+        false => Err(ShellError::CommandFailed(String::from_utf8_lossy(&image).trim().to_string())),
+    }
+}
+
+// Demo mode is left once the screen is captured, even if the device was already in demo mode.
+pub async fn adb_demo_mode_screenshot(configuration: &Configuration, device_id: &str, options: &DemoModeOptions) -> Result<Vec<u8>, DemoModeError> {
+    let previous_allowed = adb_enter_demo_mode(configuration, device_id, options).await?;
+
+    tokio::time::sleep(DEMO_MODE_SETTLE_DELAY).await;
+
+    let image = adb_screenshot(configuration, device_id).await;
+
+    adb_exit_demo_mode(configuration, device_id).await?;
+    adb_restore_demo_allowed(configuration, device_id, previous_allowed.as_deref()).await?;
+
+    image.map_err(DemoModeError::Shell)
+}
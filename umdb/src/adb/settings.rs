@@ -0,0 +1,411 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::Configuration;
+
+use super::shell::{quote, run_shell, ShellError};
+
+// Not every build of `cmd locale` has it: those of Android 13 and 14 only change the locales of apps.
+const SYSTEM_LOCALES_COMMAND: &str = "set-system-locales";
+
+static LOCALE_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z0-9-]+(,[A-Za-z0-9-]+)*$").unwrap());
+
+#[derive(Serialize)]
+pub enum SettingsError {
+    Shell(ShellError),
+    InvalidValue(String),
+    UnrecognizedOutput(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingName {
+    DarkMode,
+    FontScale,
+    DisplayDensity,
+    DisplaySize,
+    WindowAnimationScale,
+    TransitionAnimationScale,
+    AnimatorDurationScale,
+    Locale,
+    StayAwakeWhileCharging,
+    AirplaneMode,
+    Wifi,
+    MobileData,
+    RotationLock,
+    Orientation,
+    HttpProxy,
+}
+
+impl SettingName {
+    pub const ALL: [SettingName; 15] = [
+        SettingName::DarkMode,
+        SettingName::FontScale,
+        SettingName::DisplayDensity,
+        SettingName::DisplaySize,
+        SettingName::WindowAnimationScale,
+        SettingName::TransitionAnimationScale,
+        SettingName::AnimatorDurationScale,
+        SettingName::Locale,
+        SettingName::StayAwakeWhileCharging,
+        SettingName::AirplaneMode,
+        SettingName::Wifi,
+        SettingName::MobileData,
+        SettingName::RotationLock,
+        SettingName::Orientation,
+        SettingName::HttpProxy,
+    ];
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NightMode {
+    Yes,
+    No,
+    Auto,
+    Custom,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySize {
+    pub width: u32,
+    pub height: u32,
+}
+
+// Rotations of the screen when rotation is locked, clockwise from the natural orientation of the device.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+    ReversePortrait,
+    ReverseLandscape,
+}
+
+// Display overrides and the proxy are `null` when not set. Setters return the value the setting had before.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "setting", content = "value", rename_all = "snake_case")]
+pub enum SettingValue {
+    DarkMode(NightMode),
+    FontScale(f32),
+    DisplayDensity(Option<u32>),
+    DisplaySize(Option<DisplaySize>),
+    WindowAnimationScale(f32),
+    TransitionAnimationScale(f32),
+    AnimatorDurationScale(f32),
+
+    // BCP 47 language tags, such as `fr-FR`. Several can be given, separated by commas.
+    Locale(String),
+
+    // A bit field of the power sources keeping the screen on: 1 for AC, 2 for USB, 4 for wireless charging and 8 for
+    // docks. 0 lets the screen turn off.
+    StayAwakeWhileCharging(u32),
+
+    AirplaneMode(bool),
+    Wifi(bool),
+    MobileData(bool),
+    RotationLock(bool),
+    Orientation(Orientation),
+
+    // `host:port`.
+    HttpProxy(Option<String>),
+}
+
+impl SettingValue {
+    pub fn name(&self) -> SettingName {
+        match self {
+            SettingValue::DarkMode(_)                 => SettingName::DarkMode,
+            SettingValue::FontScale(_)                => SettingName::FontScale,
+            SettingValue::DisplayDensity(_)           => SettingName::DisplayDensity,
+            SettingValue::DisplaySize(_)              => SettingName::DisplaySize,
+            SettingValue::WindowAnimationScale(_)     => SettingName::WindowAnimationScale,
+            SettingValue::TransitionAnimationScale(_) => SettingName::TransitionAnimationScale,
+            SettingValue::AnimatorDurationScale(_)    => SettingName::AnimatorDurationScale,
+            SettingValue::Locale(_)                   => SettingName::Locale,
+            SettingValue::StayAwakeWhileCharging(_)   => SettingName::StayAwakeWhileCharging,
+            SettingValue::AirplaneMode(_)             => SettingName::AirplaneMode,
+            SettingValue::Wifi(_)                     => SettingName::Wifi,
+            SettingValue::MobileData(_)               => SettingName::MobileData,
+            SettingValue::RotationLock(_)             => SettingName::RotationLock,
+            SettingValue::Orientation(_)              => SettingName::Orientation,
+            SettingValue::HttpProxy(_)                => SettingName::HttpProxy,
+        }
+    }
+}
+
+// Settings that cannot be read on a device, such as dark mode before Android 10, are reported as errors on their own.
+pub async fn adb_get_settings(configuration: &Configuration, device_id: &str) -> BTreeMap<SettingName, Result<SettingValue, SettingsError>> {
+    let values = futures
+    ::future
+    ::join_all(SettingName::ALL.map(|name| adb_get_setting(configuration, device_id, name)))
+    .await;
+
+    SettingName::ALL.into_iter().zip(values).collect()
+}
+
+pub async fn adb_get_setting(configuration: &Configuration, device_id: &str, name: SettingName) -> Result<SettingValue, SettingsError> {
+    let shell = DeviceShell { configuration, device_id };
+
+    let value = match name {
+        SettingName::DarkMode => {
+            let output = shell.run("cmd uimode night").await?;
+
+            // Prints `Night mode: yes`.
+            let mode = match output.trim().rsplit(' ').next() {
+                Some("yes")    => NightMode::Yes,
+                Some("no")     => NightMode::No,
+                Some("auto")   => NightMode::Auto,
+                Some("custom") => NightMode::Custom,
+                _              => return Err(SettingsError::UnrecognizedOutput(output)),
+            };
+
+            SettingValue::DarkMode(mode)
+        },
+
+        SettingName::FontScale                => SettingValue::FontScale(shell.get_scale("system", "font_scale").await?),
+        SettingName::WindowAnimationScale     => SettingValue::WindowAnimationScale(shell.get_scale("global", "window_animation_scale").await?),
+        SettingName::TransitionAnimationScale => SettingValue::TransitionAnimationScale(shell.get_scale("global", "transition_animation_scale").await?),
+        SettingName::AnimatorDurationScale    => SettingValue::AnimatorDurationScale(shell.get_scale("global", "animator_duration_scale").await?),
+
+        // Prints `Physical density: 420`, followed by `Override density: 480` when overridden.
+        SettingName::DisplayDensity => {
+            let output = shell.run("wm density").await?;
+
+            let density = find_override(&output)
+            .map(|density| density.parse().map_err(|_| SettingsError::UnrecognizedOutput(output.clone())))
+            .transpose()?;
+
+            SettingValue::DisplayDensity(density)
+        },
+
+        SettingName::DisplaySize => {
+            let output = shell.run("wm size").await?;
+
+            let size = find_override(&output)
+            .map(|size| parse_display_size(size).ok_or_else(|| SettingsError::UnrecognizedOutput(output.clone())))
+            .transpose()?;
+
+            SettingValue::DisplaySize(size)
+        },
+
+        // Devices that were never given a locale by the user report the one they were built with.
+        SettingName::Locale => {
+            let output = shell.run("settings get system system_locales; getprop persist.sys.locale; getprop ro.product.locale").await?;
+
+            let locale = output
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && *line != "null")
+            .ok_or_else(|| SettingsError::UnrecognizedOutput(output.clone()))?;
+
+            SettingValue::Locale(locale.to_string())
+        },
+
+        SettingName::StayAwakeWhileCharging => {
+            let sources = shell
+            .get("global", "stay_on_while_plugged_in")
+            .await?
+            .map(|sources| sources.parse().map_err(|_| SettingsError::UnrecognizedOutput(sources)))
+            .transpose()?
+            .unwrap_or(0);
+
+            SettingValue::StayAwakeWhileCharging(sources)
+        },
+
+        SettingName::AirplaneMode => SettingValue::AirplaneMode(shell.get_flag("global", "airplane_mode_on").await?),
+
+        // 2 means that Wi-Fi was turned on while in airplane mode.
+        SettingName::Wifi       => SettingValue::Wifi(shell.get_flag("global", "wifi_on").await?),
+        SettingName::MobileData => SettingValue::MobileData(shell.get_flag("global", "mobile_data").await?),
+
+        // The screen follows the accelerometer unless rotation is locked.
+        SettingName::RotationLock => SettingValue::RotationLock(!shell.get_flag("system", "accelerometer_rotation").await?),
+
+        SettingName::Orientation => {
+            let orientation = match shell.get("system", "user_rotation").await?.as_deref() {
+                None | Some("0") => Orientation::Portrait,
+                Some("1")        => Orientation::Landscape,
+                Some("2")        => Orientation::ReversePortrait,
+                Some("3")        => Orientation::ReverseLandscape,
+                Some(other)      => return Err(SettingsError::UnrecognizedOutput(other.to_string())),
+            };
+
+            SettingValue::Orientation(orientation)
+        },
+
+        // `:0` is how the proxy is removed without rebooting the device.
+        SettingName::HttpProxy => SettingValue::HttpProxy(shell.get("global", "http_proxy").await?.filter(|proxy| proxy != ":0")),
+    };
+
+    Ok(value)
+}
+
+// Returns the previous value of the setting.
+pub async fn adb_set_setting(configuration: &Configuration, device_id: &str, value: &SettingValue) -> Result<SettingValue, SettingsError> {
+    let shell = DeviceShell { configuration, device_id };
+
+    let previous_value = adb_get_setting(configuration, device_id, value.name()).await?;
+
+    let command = match value {
+        SettingValue::DarkMode(mode) => format!("cmd uimode night {}", match mode {
+            NightMode::Yes    => "yes",
+            NightMode::No     => "no",
+            NightMode::Auto   => "auto",
+            NightMode::Custom => "custom",
+        }),
+
+        SettingValue::FontScale(scale)                => put_scale("system", "font_scale", *scale)?,
+        SettingValue::WindowAnimationScale(scale)     => put_scale("global", "window_animation_scale", *scale)?,
+        SettingValue::TransitionAnimationScale(scale) => put_scale("global", "transition_animation_scale", *scale)?,
+        SettingValue::AnimatorDurationScale(scale)    => put_scale("global", "animator_duration_scale", *scale)?,
+
+        SettingValue::DisplayDensity(Some(density)) => format!("wm density {density}"),
+        SettingValue::DisplayDensity(None)          => "wm density reset".to_string(),
+        SettingValue::DisplaySize(Some(size))       => format!("wm size {}x{}", size.width, size.height),
+        SettingValue::DisplaySize(None)             => "wm size reset".to_string(),
+
+        // Builds whose `cmd locale` can change the system locales apply them at once. Others fall back to the
+        // `system_locales` setting, which apps only read once restarted.
+        SettingValue::Locale(locale) => {
+            if !LOCALE_REGEXP.is_match(locale) {
+                return Err(SettingsError::InvalidValue(locale.clone()));
+            }
+
+            let help = shell.run("cmd locale help 2>&1 || true").await?;
+
+            match help.contains(SYSTEM_LOCALES_COMMAND) {
+                true  => format!("cmd locale {SYSTEM_LOCALES_COMMAND} {}", quote(locale)),
+                false => format!("settings put system system_locales {}", quote(locale)),
+            }
+        },
+
+        SettingValue::StayAwakeWhileCharging(sources) => {
+            if *sources > 15 {
+                return Err(SettingsError::InvalidValue(sources.to_string()));
+            }
+
+            format!("settings put global stay_on_while_plugged_in {sources}")
+        },
+
+        SettingValue::AirplaneMode(enabled) => format!("cmd connectivity airplane-mode {}", enable_argument(*enabled)),
+        SettingValue::Wifi(enabled)         => format!("svc wifi {}", enable_argument(*enabled)),
+        SettingValue::MobileData(enabled)   => format!("svc data {}", enable_argument(*enabled)),
+
+        SettingValue::RotationLock(locked) => format!("settings put system accelerometer_rotation {}", if *locked { 0 } else { 1 }),
+
+        SettingValue::Orientation(orientation) => format!("settings put system user_rotation {}", match orientation {
+            Orientation::Portrait         => 0,
+            Orientation::Landscape        => 1,
+            Orientation::ReversePortrait  => 2,
+            Orientation::ReverseLandscape => 3,
+        }),
+
+        SettingValue::HttpProxy(Some(proxy)) => format!("settings put global http_proxy {}", quote(proxy)),
+        SettingValue::HttpProxy(None)        => "settings put global http_proxy :0".to_string(),
+    };
+
+    let output = shell.run(&command).await?;
+
+    // Some of these commands report errors with a zero exit code.
+    if output.contains("Error") || output.contains("Exception") || output.contains("Unknown command") {
+        return Err(SettingsError::Shell(ShellError::CommandFailed(output.trim().to_string())));
+    }
+
+    Ok(previous_value)
+}
+
+struct DeviceShell<'a> {
+    configuration: &'a Configuration,
+    device_id: &'a str,
+}
+
+impl DeviceShell<'_> {
+    async fn run(&self, command: &str) -> Result<String, SettingsError> {
+        run_shell(self.configuration, self.device_id, command).await.map_err(SettingsError::Shell)
+    }
+
+    // Settings that were never set are printed as `null`.
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<String>, SettingsError> {
+        let output = self.run(&format!("settings get {namespace} {key}")).await?;
+
+        let value = output.trim();
+
+        Ok((value != "null" && !value.is_empty()).then(|| value.to_string()))
+    }
+
+    async fn get_flag(&self, namespace: &str, key: &str) -> Result<bool, SettingsError> {
+        Ok(self.get(namespace, key).await?.is_some_and(|value| value != "0"))
+    }
+
+    // Scales default to 1 when never set.
+    async fn get_scale(&self, namespace: &str, key: &str) -> Result<f32, SettingsError> {
+        match self.get(namespace, key).await? {
+            Some(value) => value.parse().map_err(|_| SettingsError::UnrecognizedOutput(value)),
+            None        => Ok(1.0),
+        }
+    }
+}
+
+fn put_scale(namespace: &str, key: &str, scale: f32) -> Result<String, SettingsError> {
+    if !scale.is_finite() || scale < 0.0 {
+        return Err(SettingsError::InvalidValue(scale.to_string()));
+    }
+
+    Ok(format!("settings put {namespace} {key} {scale}"))
+}
+
+fn enable_argument(enabled: bool) -> &'static str {
+    match enabled {
+        true  => "enable",
+        false => "disable",
+    }
+}
+
+fn find_override(output: &str) -> Option<&str> {
+    output
+    .lines()
+    .find_map(|line| line.trim().strip_prefix("Override "))
+    .and_then(|line| line.split_once(": "))
+    .map(|(_, value)| value.trim())
+}
+
+pub fn parse_display_size(size: &str) -> Option<DisplaySize> {
+    let (width, height) = size.split_once('x')?;
+
+    Some(DisplaySize { width: width.parse().ok()?, height: height.parse().ok()? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_override, parse_display_size};
+
+    // `wm size` and `wm density` on a Pixel 7, with and without overrides.
+    const WM_SIZE_OUTPUT: &str = "Physical size: 1080x2400\n";
+
+    const OVERRIDDEN_WM_SIZE_OUTPUT: &str = "Physical size: 1080x2400\nOverride size: 720x1600\n";
+
+    const OVERRIDDEN_WM_DENSITY_OUTPUT: &str = "Physical density: 420\nOverride density: 560\n";
+
+    #[test]
+    fn finds_display_overrides() {
+        assert_eq!(find_override(WM_SIZE_OUTPUT), None);
+        assert_eq!(find_override(OVERRIDDEN_WM_SIZE_OUTPUT), Some("720x1600"));
+        assert_eq!(find_override(OVERRIDDEN_WM_DENSITY_OUTPUT), Some("560"));
+
+        // adb shell output ends its lines with `\r` on older devices.
+        assert_eq!(find_override("Physical density: 480\r\nOverride density: 320\r\n"), Some("320"));
+    }
+
+    #[test]
+    fn parses_display_size() {
+        let size = parse_display_size("720x1600").unwrap();
+
+        assert_eq!((size.width, size.height), (720, 1600));
+
+        assert!(parse_display_size("720").is_none());
+        assert!(parse_display_size("720x").is_none());
+        assert!(parse_display_size("-720x1600").is_none());
+    }
+}
@@ -0,0 +1,51 @@
+use std::process::Output;
+
+use serde::Serialize;
+
+use crate::core::Configuration;
+
+use super::{process::run_adb, target::device_arguments};
+
+#[derive(Debug, Serialize)]
+pub enum ShellError {
+    CannotRunProcess(String),
+    CommandFailed(String),
+    DebugBridgePathMissing,
+    DeviceUnresponsive,
+}
+
+// adb joins the arguments of `adb shell` with spaces and hands them to the device shell, so each one has to be quoted.
+pub fn quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\'', r"'\''"))
+}
+
+// Runs a shell command line on the device and returns what it printed.
+pub async fn run_shell(configuration: &Configuration, device_id: &str, command: &str) -> Result<String, ShellError> {
+    let output = run_device_command(configuration, device_id, "shell", command).await?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Unlike `adb shell`, `adb exec-out` does not mangle binary output such as images.
+pub async fn run_exec_out(configuration: &Configuration, device_id: &str, command: &str) -> Result<Vec<u8>, ShellError> {
+    Ok(run_device_command(configuration, device_id, "exec-out", command).await?.stdout)
+}
+
+async fn run_device_command(configuration: &Configuration, device_id: &str, subcommand: &str, command: &str) -> Result<Output, ShellError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(ShellError::DebugBridgePathMissing)?;
+
+    let output = run_adb(adb_command, &device_arguments(device_id, &[subcommand, command]), &configuration.timeouts.shell).await?;
+
+    if !output.status.success() {
+        return Err(ShellError::CommandFailed(
+            format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+            .trim()
+            .to_string()
+        ));
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,18 @@
+const TRANSPORT_ID_PREFIX: &str = "transport_id:";
+
+// Device ids are serials, or `transport_id:<id>` as printed by `adb devices -l` to tell apart devices sharing a serial.
+pub fn device_arguments<'a>(device_id: &'a str, arguments: &[&'a str]) -> Vec<&'a str> {
+    let selection = match device_id.strip_prefix(TRANSPORT_ID_PREFIX) {
+        Some(transport_id) => ["-t", transport_id],
+        None               => ["-s", device_id],
+    };
+
+    selection
+    .into_iter()
+    .chain(arguments.iter().copied())
+    .collect()
+}
+
+pub fn transport_device_id(transport_id: u64) -> String {
+    format!("{TRANSPORT_ID_PREFIX}{transport_id}")
+}
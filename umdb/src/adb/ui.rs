@@ -0,0 +1,325 @@
+use std::{sync::LazyLock, time::{Duration, Instant}};
+
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use crate::{common::{input::{InputAction, InputEvent}, ui::{Bounds, ScrollDirection, UiAction, UiActionError, UiActionRequest, UiDumpError, UiHierarchy, UiNode, UiSelector}}, core::Configuration};
+
+use super::{input::{adb_clear_text, adb_send_input}, process::run_adb, target::device_arguments};
+
+const DUMP_FILE_PATH: &str = "/sdcard/umdb-window-dump.xml";
+
+const ELEMENT_POLLING_INTERVAL: Duration = Duration::from_millis(500);
+
+// Lets the screen settle after a scroll, so that the next dump does not catch it moving.
+const SCROLL_SETTLING_DELAY: Duration = Duration::from_millis(300);
+
+const DEFAULT_SCROLL_TIMEOUT_MS: u64 = 30_000;
+
+static BOUNDS_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\[(-?\d+),(-?\d+)\]\[(-?\d+),(-?\d+)\]$").unwrap());
+
+// Dumping to the terminal avoids writing to the device storage, but some versions print nothing there: the dump then
+// goes through a file that is read back and removed.
+pub async fn adb_dump_ui(configuration: &Configuration, device_id: &str) -> Result<UiHierarchy, UiDumpError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(UiDumpError::DebugBridgePathMissing)?;
+
+    let scripts = [
+        "uiautomator dump /dev/tty".to_string(),
+        format!("uiautomator dump {DUMP_FILE_PATH} >/dev/null && cat {DUMP_FILE_PATH}; rm -f {DUMP_FILE_PATH}"),
+    ];
+
+    let mut output = String::new();
+
+    for script in scripts {
+        let result = run_adb(adb_command, &device_arguments(device_id, &["exec-out", &script]), &configuration.timeouts.shell).await?;
+
+        output = String::from_utf8_lossy(&result.stdout).into_owned();
+
+        if output.contains("<hierarchy") {
+            return parse_ui_dump(&output);
+        }
+    }
+
+    Err(UiDumpError::CommandFailed(output.trim().to_string()))
+}
+
+// Finds the element, acts on it, and returns it as it was before the action.
+pub async fn adb_ui_action(configuration: &Configuration, device_id: &str, request: &UiActionRequest) -> Result<UiNode, UiActionError> {
+    let node = match &request.action {
+        Some(UiAction::ScrollUntilVisible { direction, max_scrolls }) => {
+            let timeout = Duration::from_millis(request.timeout_ms.unwrap_or(DEFAULT_SCROLL_TIMEOUT_MS));
+
+            scroll_until_visible(configuration, device_id, &request.selector, *direction, *max_scrolls, timeout).await?
+        },
+
+        _ => wait_for_element(configuration, device_id, &request.selector, request.timeout_ms).await?,
+    };
+
+    match &request.action {
+        None | Some(UiAction::ScrollUntilVisible { .. }) => {},
+
+        Some(UiAction::Tap) => {
+            let (x, y) = tap_point(&node)?;
+
+            send_input(configuration, device_id, InputAction::Tap { x, y }).await?;
+        },
+
+        Some(UiAction::LongPress { duration_ms }) => {
+            let (x, y) = tap_point(&node)?;
+
+            send_input(configuration, device_id, InputAction::LongPress { x, y, duration_ms: *duration_ms }).await?;
+        },
+
+        // The field is focused by tapping it, then emptied from its end before typing.
+        Some(UiAction::SetText { text }) => {
+            let (x, y) = tap_point(&node)?;
+
+            send_input(configuration, device_id, InputAction::Tap { x, y }).await?;
+
+            let length = node.text.as_deref().map(|text| text.chars().count()).unwrap_or(0);
+
+            if length > 0 {
+                adb_clear_text(configuration, device_id, length).await.map_err(UiActionError::Input)?;
+            }
+
+            send_input(configuration, device_id, InputAction::Text { text: text.clone() }).await?;
+        },
+    }
+
+    Ok(node)
+}
+
+async fn wait_for_element(configuration: &Configuration, device_id: &str, selector: &UiSelector, timeout_ms: Option<u64>) -> Result<UiNode, UiActionError> {
+    let started_at = Instant::now();
+    let timeout    = Duration::from_millis(timeout_ms.unwrap_or(0));
+
+    loop {
+        let hierarchy = adb_dump_ui(configuration, device_id).await.map_err(UiActionError::Dump)?;
+
+        if let Some(node) = hierarchy.find(selector)? {
+            return Ok(node.clone());
+        }
+
+        if started_at.elapsed() >= timeout {
+            return Err(UiActionError::ElementNotFound);
+        }
+
+        tokio::time::sleep(ELEMENT_POLLING_INTERVAL).await;
+    }
+}
+
+// Swipes within the first scrollable container, or the whole screen when there is none. Stops at the end of the
+// list, once a swipe no longer changes the screen.
+async fn scroll_until_visible(configuration: &Configuration, device_id: &str, selector: &UiSelector, direction: ScrollDirection, max_scrolls: Option<u32>, timeout: Duration) -> Result<UiNode, UiActionError> {
+    let started_at = Instant::now();
+
+    let mut previous_hierarchy = None::<UiHierarchy>;
+    let mut scroll_count       = 0;
+
+    loop {
+        let hierarchy = adb_dump_ui(configuration, device_id).await.map_err(UiActionError::Dump)?;
+
+        if let Some(node) = hierarchy.find(selector)? {
+            return Ok(node.clone());
+        }
+
+        let list_ended = previous_hierarchy.as_ref() == Some(&hierarchy);
+
+        if list_ended || max_scrolls == Some(scroll_count) || started_at.elapsed() >= timeout {
+            return Err(UiActionError::ElementNotFound);
+        }
+
+        let container = hierarchy
+        .first_scrollable()
+        .or(hierarchy.nodes.first())
+        .and_then(|node| node.bounds)
+        .ok_or(UiActionError::ElementNotFound)?;
+
+        send_input(configuration, device_id, scroll_input(container, direction)).await?;
+
+        scroll_count += 1;
+        previous_hierarchy = Some(hierarchy);
+
+        tokio::time::sleep(SCROLL_SETTLING_DELAY).await;
+    }
+}
+
+// Swipes over half of the container, so that the content does not fling too far past the element.
+fn scroll_input(container: Bounds, direction: ScrollDirection) -> InputAction {
+    let (center_x, center_y) = container.center();
+
+    let quarter_width  = (container.right - container.left) / 4;
+    let quarter_height = (container.bottom - container.top) / 4;
+
+    let ((from_x, from_y), (to_x, to_y)) = match direction {
+        ScrollDirection::Down  => ((center_x, center_y + quarter_height), (center_x, center_y - quarter_height)),
+        ScrollDirection::Up    => ((center_x, center_y - quarter_height), (center_x, center_y + quarter_height)),
+        ScrollDirection::Right => ((center_x + quarter_width, center_y), (center_x - quarter_width, center_y)),
+        ScrollDirection::Left  => ((center_x - quarter_width, center_y), (center_x + quarter_width, center_y)),
+    };
+
+    InputAction::Swipe {
+        from_x:      from_x.max(0) as u32,
+        from_y:      from_y.max(0) as u32,
+        to_x:        to_x.max(0) as u32,
+        to_y:        to_y.max(0) as u32,
+        duration_ms: Some(500),
+    }
+}
+
+fn tap_point(node: &UiNode) -> Result<(u32, u32), UiActionError> {
+    let (x, y) = node
+    .bounds
+    .as_ref()
+    .map(Bounds::center)
+    .ok_or(UiActionError::ElementWithoutBounds)?;
+
+    Ok((x.max(0) as u32, y.max(0) as u32))
+}
+
+async fn send_input(configuration: &Configuration, device_id: &str, action: InputAction) -> Result<(), UiActionError> {
+    let event = InputEvent { action, source: None, display_id: None };
+
+    adb_send_input(configuration, device_id, &event).await.map_err(UiActionError::Input)
+}
+
+// uiautomator appends a status line such as `UI hierchary dumped to: /dev/tty` to the XML document.
+pub fn parse_ui_dump(output: &str) -> Result<UiHierarchy, UiDumpError> {
+    let start = output.find("<?xml").or_else(|| output.find("<hierarchy")).unwrap_or(0);
+
+    let end = output
+    .rfind("</hierarchy>")
+    .map(|position| position + "</hierarchy>".len())
+    .unwrap_or(output.len());
+
+    let document = Document::parse(&output[start..end]).map_err(|error| UiDumpError::MalformedDump(error.to_string()))?;
+
+    let root = document.root_element();
+
+    Ok(UiHierarchy {
+        rotation: root.attribute("rotation").and_then(|rotation| rotation.parse().ok()).unwrap_or(0),
+        nodes:    root.children().filter(|node| node.has_tag_name("node")).map(parse_node).collect(),
+    })
+}
+
+fn parse_node(node: Node) -> UiNode {
+    let string = |name: &str| node.attribute(name).filter(|value| !value.is_empty()).map(str::to_string);
+    let flag   = |name: &str| node.attribute(name) == Some("true");
+
+    UiNode {
+        index:          node.attribute("index").and_then(|index| index.parse().ok()).unwrap_or(0),
+        class:          node.attribute("class").unwrap_or_default().to_string(),
+        package:        string("package"),
+        resource_id:    string("resource-id"),
+        text:           string("text"),
+        content_desc:   string("content-desc"),
+        bounds:         node.attribute("bounds").and_then(parse_bounds),
+        clickable:      flag("clickable"),
+        long_clickable: flag("long-clickable"),
+        scrollable:     flag("scrollable"),
+        checkable:      flag("checkable"),
+        checked:        flag("checked"),
+        enabled:        flag("enabled"),
+        focusable:      flag("focusable"),
+        focused:        flag("focused"),
+        selected:       flag("selected"),
+        password:       flag("password"),
+        children:       node.children().filter(|child| child.has_tag_name("node")).map(parse_node).collect(),
+    }
+}
+
+// Bounds look like `[0,63][1080,210]`.
+fn parse_bounds(bounds: &str) -> Option<Bounds> {
+    let captures = BOUNDS_REGEXP.captures(bounds)?;
+
+    let coordinate = |index: usize| captures[index].parse::<i32>().ok();
+
+    Some(Bounds {
+        left:   coordinate(1)?,
+        top:    coordinate(2)?,
+        right:  coordinate(3)?,
+        bottom: coordinate(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::ui::UiSelector;
+
+    use super::{parse_bounds, parse_ui_dump};
+
+    // `uiautomator dump /dev/tty` on a Pixel 6 running Android 13, on the settings of an app. Trimmed.
+    const UI_DUMP: &str = r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><hierarchy rotation="0"><node index="0" text="" resource-id="" class="android.widget.FrameLayout" package="com.example" content-desc="" checkable="false" checked="false" clickable="false" enabled="true" focusable="false" focused="false" scrollable="false" long-clickable="false" password="false" selected="false" bounds="[0,0][1080,2400]"><node index="0" text="" resource-id="com.example:id/settings_list" class="androidx.recyclerview.widget.RecyclerView" package="com.example" content-desc="" checkable="false" checked="false" clickable="false" enabled="true" focusable="true" focused="false" scrollable="true" long-clickable="false" password="false" selected="false" bounds="[0,210][1080,2337]"><node index="0" text="Notifications" resource-id="android:id/title" class="android.widget.TextView" package="com.example" content-desc="" checkable="false" checked="false" clickable="false" enabled="true" focusable="false" focused="false" scrollable="false" long-clickable="false" password="false" selected="false" bounds="[42,252][1038,315]" /><node index="1" text="" resource-id="com.example:id/dark_mode" class="android.widget.Switch" package="com.example" content-desc="Dark mode" checkable="true" checked="true" clickable="true" enabled="true" focusable="true" focused="false" scrollable="false" long-clickable="false" password="false" selected="false" bounds="[891,336][1038,420]" /></node></node></hierarchy>UI hierchary dumped to: /dev/tty
+"#;
+
+    #[test]
+    fn parses_ui_dump() {
+        let hierarchy = parse_ui_dump(UI_DUMP).ok().unwrap();
+
+        assert_eq!(hierarchy.rotation, 0);
+        assert_eq!(hierarchy.nodes.len(), 1);
+
+        let list = &hierarchy.nodes[0].children[0];
+
+        assert!(list.scrollable);
+        assert_eq!(list.resource_id.as_deref(), Some("com.example:id/settings_list"));
+        assert_eq!(list.children.len(), 2);
+
+        let title = &list.children[0];
+
+        assert_eq!(title.text.as_deref(), Some("Notifications"));
+        assert_eq!(title.content_desc, None);
+
+        let bounds = title.bounds.unwrap();
+
+        assert_eq!((bounds.left, bounds.top, bounds.right, bounds.bottom), (42, 252, 1038, 315));
+        assert_eq!(bounds.center(), (540, 283));
+    }
+
+    #[test]
+    fn finds_nodes_of_ui_dump() {
+        let hierarchy = parse_ui_dump(UI_DUMP).ok().unwrap();
+
+        let selector = UiSelector { resource_id: Some("dark_mode".to_string()), ..UiSelector::default() };
+
+        let switch = hierarchy.find(&selector).ok().flatten().unwrap();
+
+        assert!(switch.checked);
+        assert_eq!(switch.content_desc.as_deref(), Some("Dark mode"));
+
+        let scrollable = hierarchy.first_scrollable().unwrap();
+
+        assert_eq!(scrollable.class, "androidx.recyclerview.widget.RecyclerView");
+
+        // Only the list and the switch can be interacted with.
+        let compact = hierarchy.compact();
+
+        assert_eq!(compact.nodes.len(), 1);
+        assert_eq!(compact.nodes[0].children.len(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_ui_dump() {
+        assert!(parse_ui_dump("ERROR: could not get idle state.").is_err());
+        assert!(parse_ui_dump("<hierarchy rotation=\"0\"><node index=\"0\"></hierarchy>").is_err());
+    }
+
+    #[test]
+    fn parses_bounds() {
+        let bounds = parse_bounds("[0,63][1080,210]").unwrap();
+
+        assert_eq!((bounds.left, bounds.top, bounds.right, bounds.bottom), (0, 63, 1080, 210));
+
+        // Views partly off screen.
+        let bounds = parse_bounds("[-120,2337][960,2520]").unwrap();
+
+        assert_eq!((bounds.left, bounds.top), (-120, 2337));
+
+        assert!(parse_bounds("").is_none());
+        assert!(parse_bounds("[0,63][1080]").is_none());
+        assert!(parse_bounds("[0,63][1080,99999999999]").is_none());
+    }
+}
@@ -0,0 +1,258 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::Configuration;
+
+use super::{activity::{adb_activity_state, read_foreground_activity}, logcat::{adb_device_time, adb_logcat_since}, process::run_adb, shell::{quote, run_shell}, target::device_arguments};
+
+const DEFAULT_WAIT_TIMEOUT_MS: u64 = 60_000;
+
+const CONDITION_POLLING_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Serialize)]
+pub enum WaitError {
+    DebugBridgePathMissing,
+    NoCondition,
+    InvalidLogRegex(String),
+    TimedOut,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WaitCondition {
+    // The device is listed in the `device` state, after a reboot or a connection.
+    DeviceOnline,
+
+    BootCompleted,
+
+    // See `ForegroundActivity::matches` for the ways activities can be given.
+    Activity {
+        activity: String,
+    },
+
+    // Only looks at what is logged once the wait started.
+    LogMatches {
+        pattern: String,
+    },
+
+    PackageInstalled {
+        package: String,
+    },
+
+    ScreenUnlocked,
+}
+
+#[derive(Deserialize)]
+pub struct WaitRequest {
+    pub conditions: Vec<WaitCondition>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct WaitOutcome {
+    pub condition: WaitCondition,
+
+    // Position of the condition in the request.
+    pub index: usize,
+
+    pub timestamp: u64,
+    pub elapsed_ms: u64,
+
+    // The matching line of log conditions.
+    pub log_line: Option<String>,
+}
+
+// Returns as soon as one of the conditions holds. The device does not need to be reachable while waiting: failing
+// to check a condition only means that it does not hold yet.
+pub async fn adb_wait(configuration: &Configuration, device_id: &str, request: &WaitRequest) -> Result<WaitOutcome, WaitError> {
+    let adb_command = configuration
+    .adb_command
+    .as_deref()
+    .ok_or(WaitError::DebugBridgePathMissing)?;
+
+    let log_regexps = compile_log_regexps(request)?;
+
+    let checks_logs = log_regexps.iter().any(Option::is_some);
+    let started_at  = Instant::now();
+
+    // Read once the device can be reached.
+    let mut started_at_device_time = None::<String>;
+
+    // Checks can hang as long as adb lets them, so the deadline also cuts them short.
+    let polling = async {
+        loop {
+            if checks_logs && started_at_device_time.is_none() {
+                started_at_device_time = adb_device_time(configuration, device_id).await.ok();
+            }
+
+            for (index, condition) in request.conditions.iter().enumerate() {
+                let check = ConditionCheck {
+                    log_regexp:             log_regexps[index].as_ref(),
+                    started_at_device_time: started_at_device_time.as_deref(),
+                    configuration,
+                    adb_command,
+                    device_id,
+                };
+
+                if let Some(log_line) = check.run(condition).await {
+                    return WaitOutcome {
+                        condition:  condition.clone(),
+                        timestamp:  SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_millis() as u64,
+                        elapsed_ms: started_at.elapsed().as_millis() as u64,
+                        index,
+                        log_line,
+                    };
+                }
+            }
+
+            tokio::time::sleep(CONDITION_POLLING_INTERVAL).await;
+        }
+    };
+
+    tokio::time::timeout(wait_timeout(request), polling)
+    .await
+    .map_err(|_| WaitError::TimedOut)
+}
+
+fn wait_timeout(request: &WaitRequest) -> Duration {
+    Duration::from_millis(request.timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS))
+}
+
+// One entry per condition, set for log conditions.
+fn compile_log_regexps(request: &WaitRequest) -> Result<Vec<Option<Regex>>, WaitError> {
+    if request.conditions.is_empty() {
+        return Err(WaitError::NoCondition);
+    }
+
+    request
+    .conditions
+    .iter()
+    .map(|condition| match condition {
+        WaitCondition::LogMatches { pattern } => Regex::new(pattern).map(Some).map_err(|error| WaitError::InvalidLogRegex(error.to_string())),
+
+        _ => Ok(None),
+    })
+    .collect()
+}
+
+struct ConditionCheck<'a> {
+    configuration: &'a Configuration,
+    adb_command: &'a str,
+    device_id: &'a str,
+    log_regexp: Option<&'a Regex>,
+    started_at_device_time: Option<&'a str>,
+}
+
+impl ConditionCheck<'_> {
+    // `None` when the condition does not hold, otherwise the matching line of log conditions.
+    async fn run(&self, condition: &WaitCondition) -> Option<Option<String>> {
+        let configuration = self.configuration;
+        let device_id     = self.device_id;
+
+        let holds = match condition {
+            WaitCondition::DeviceOnline => {
+                run_adb(self.adb_command, &device_arguments(device_id, &["get-state"]), &configuration.timeouts.connection)
+                .await
+                .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "device")
+            },
+
+            WaitCondition::BootCompleted => {
+                run_shell(configuration, device_id, "getprop sys.boot_completed")
+                .await
+                .is_ok_and(|output| output.trim() == "1")
+            },
+
+            WaitCondition::Activity { activity } => {
+                read_foreground_activity(configuration, device_id)
+                .await
+                .is_ok_and(|foreground_activity| foreground_activity.matches(activity))
+            },
+
+            WaitCondition::LogMatches { .. } => {
+                let (log_regexp, device_time) = self.log_regexp.zip(self.started_at_device_time)?;
+
+                let logs = adb_logcat_since(configuration, device_id, device_time).await.ok()?;
+
+                return logs
+                .lines()
+                .find(|line| log_regexp.is_match(line))
+                .map(|line| Some(line.to_string()));
+            },
+
+            // Prints `package:/data/app/.../base.apk`, and fails when the package is not installed.
+            WaitCondition::PackageInstalled { package } => {
+                run_shell(configuration, device_id, &format!("pm path {}", quote(package)))
+                .await
+                .is_ok_and(|output| output.trim_start().starts_with("package:"))
+            },
+
+            WaitCondition::ScreenUnlocked => {
+                adb_activity_state(configuration, device_id)
+                .await
+                .is_ok_and(|state| state.screen_on == Some(true) && state.keyguard_showing == Some(false))
+            },
+        };
+
+        holds.then_some(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{compile_log_regexps, wait_timeout, WaitCondition, WaitError, WaitRequest};
+
+    fn parse_request(body: &str) -> WaitRequest {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn parses_wait_requests() {
+        let request = parse_request(r#"{
+            "conditions": [
+                { "type": "device_online" },
+                { "type": "activity", "activity": "com.example.app/.MainActivity" },
+                { "type": "log_matches", "pattern": "Displayed com\\.example" },
+                { "type": "package_installed", "package": "com.example.app" },
+                { "type": "screen_unlocked" }
+            ],
+            "timeout_ms": 5000
+        }"#);
+
+        assert!(matches!(request.conditions[0], WaitCondition::DeviceOnline));
+        assert!(matches!(&request.conditions[1], WaitCondition::Activity { activity } if activity == "com.example.app/.MainActivity"));
+        assert!(matches!(&request.conditions[2], WaitCondition::LogMatches { pattern } if pattern == r"Displayed com\.example"));
+        assert!(matches!(&request.conditions[3], WaitCondition::PackageInstalled { package } if package == "com.example.app"));
+        assert!(matches!(request.conditions[4], WaitCondition::ScreenUnlocked));
+        assert_eq!(wait_timeout(&request), Duration::from_millis(5000));
+
+        let request = parse_request(r#"{ "conditions": [{ "type": "boot_completed" }] }"#);
+
+        assert_eq!(wait_timeout(&request), Duration::from_secs(60));
+        assert!(serde_json::from_str::<WaitRequest>(r#"{ "conditions": [{ "type": "reboot" }] }"#).is_err());
+    }
+
+    #[test]
+    fn compiles_log_regexps_of_log_conditions_only() {
+        let request = parse_request(r#"{ "conditions": [{ "type": "boot_completed" }, { "type": "log_matches", "pattern": "FATAL EXCEPTION" }] }"#);
+
+        let log_regexps = compile_log_regexps(&request).ok().unwrap();
+
+        assert!(log_regexps[0].is_none());
+        assert!(log_regexps[1].as_ref().is_some_and(|regexp| regexp.is_match("E AndroidRuntime: FATAL EXCEPTION: main")));
+    }
+
+    #[test]
+    fn rejects_requests_that_cannot_be_waited_for() {
+        let request = parse_request(r#"{ "conditions": [] }"#);
+
+        assert!(matches!(compile_log_regexps(&request), Err(WaitError::NoCondition)));
+
+        let request = parse_request(r#"{ "conditions": [{ "type": "log_matches", "pattern": "(unclosed" }] }"#);
+
+        assert!(matches!(compile_log_regexps(&request), Err(WaitError::InvalidLogRegex(_))));
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use super::binary::{chunk_header_at, parse_string_pool, u16_at, u32_at, u8_at, MalformedData, STRING_POOL_CHUNK};
+
+const TABLE_CHUNK:         u16 = 0x0002;
+const TABLE_PACKAGE_CHUNK: u16 = 0x0200;
+const TABLE_TYPE_CHUNK:    u16 = 0x0201;
+
+const ENTRY_FLAG_COMPLEX: u16 = 0x0001;
+const ENTRY_FLAG_COMPACT: u16 = 0x0008;
+
+const TYPE_FLAG_SPARSE:   u8 = 0x01;
+const TYPE_FLAG_OFFSET16: u8 = 0x02;
+
+const NO_ENTRY:          u32 = 0xffff_ffff;
+const NO_ENTRY_OFFSET16: u16 = 0xffff;
+
+pub const VALUE_TYPE_REFERENCE: u8 = 0x01;
+pub const VALUE_TYPE_STRING:    u8 = 0x03;
+pub const VALUE_TYPE_BOOLEAN:   u8 = 0x12;
+
+#[derive(Clone, Copy)]
+pub struct ResourceValue {
+    pub data_type: u8,
+    pub data:      u32,
+}
+
+// Only simple values are decoded: links declared in manifests reference strings and booleans, never styles or arrays.
+#[derive(Default)]
+pub struct ResourceTable {
+    strings: Vec<String>,
+    values:  BTreeMap<u32, ResourceValue>,
+}
+
+impl ResourceTable {
+    pub fn parse(data: &[u8]) -> Result<ResourceTable, MalformedData> {
+        let header = chunk_header_at(data, 0)?;
+
+        if header.chunk_type != TABLE_CHUNK {
+            return Err(MalformedData);
+        }
+
+        let mut table  = ResourceTable::default();
+        let mut offset = header.header_size;
+
+        while offset < header.size {
+            let chunk = chunk_header_at(data, offset)?;
+
+            match chunk.chunk_type {
+                STRING_POOL_CHUNK   => table.strings = parse_string_pool(data, offset)?,
+                TABLE_PACKAGE_CHUNK => table.parse_package(data, offset, chunk.header_size, chunk.size)?,
+
+                _ => {},
+            }
+
+            offset += chunk.size;
+        }
+
+        Ok(table)
+    }
+
+    pub fn string(&self, index: u32) -> Option<&str> {
+        self.strings.get(index as usize).map(String::as_str)
+    }
+
+    pub fn value(&self, resource_id: u32) -> Option<ResourceValue> {
+        self.values.get(&resource_id).copied()
+    }
+
+    fn parse_package(&mut self, data: &[u8], start: usize, header_size: usize, size: usize) -> Result<(), MalformedData> {
+        let package_id = u32_at(data, start + 8)?;
+
+        let mut offset = start + header_size;
+
+        while offset < start + size {
+            let chunk = chunk_header_at(data, offset)?;
+
+            if chunk.chunk_type == TABLE_TYPE_CHUNK {
+                self.parse_type(data, offset, chunk.header_size, package_id)?;
+            }
+
+            offset += chunk.size;
+        }
+
+        Ok(())
+    }
+
+    fn parse_type(&mut self, data: &[u8], start: usize, header_size: usize, package_id: u32) -> Result<(), MalformedData> {
+        let type_id       = u8_at(data, start + 8)? as u32;
+        let flags         = u8_at(data, start + 9)?;
+        let entry_count   = u32_at(data, start + 12)? as usize;
+        let entries_start = start + u32_at(data, start + 16)? as usize;
+
+        // A configuration made only of zeroes after its size field is the default configuration.
+        let config_size       = u32_at(data, start + 20)? as usize;
+        let is_default_config = data
+        .get(start + 24..start + 20 + config_size)
+        .is_some_and(|config| config.iter().all(|&byte| byte == 0));
+
+        let offsets_start = start + header_size;
+
+        for index in 0..entry_count {
+            let (entry_index, entry_offset) = if flags & TYPE_FLAG_SPARSE != 0 {
+                (u16_at(data, offsets_start + index * 4)? as u32, u16_at(data, offsets_start + index * 4 + 2)? as u32 * 4)
+            } else if flags & TYPE_FLAG_OFFSET16 != 0 {
+                match u16_at(data, offsets_start + index * 2)? {
+                    NO_ENTRY_OFFSET16 => continue,
+                    offset            => (index as u32, offset as u32 * 4),
+                }
+            } else {
+                match u32_at(data, offsets_start + index * 4)? {
+                    NO_ENTRY => continue,
+                    offset   => (index as u32, offset),
+                }
+            };
+
+            let resource_id = (package_id << 24) | (type_id << 16) | entry_index;
+
+            // Values from the default configuration win, otherwise the first configuration seen is kept.
+            if self.values.contains_key(&resource_id) && !is_default_config {
+                continue;
+            }
+
+            if let Some(value) = parse_entry(data, entries_start + entry_offset as usize)? {
+                self.values.insert(resource_id, value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_entry(data: &[u8], offset: usize) -> Result<Option<ResourceValue>, MalformedData> {
+    let flags = u16_at(data, offset + 2)?;
+
+    if flags & ENTRY_FLAG_COMPACT != 0 {
+        return Ok(Some(ResourceValue { data_type: (flags >> 8) as u8, data: u32_at(data, offset + 4)? }));
+    }
+
+    if flags & ENTRY_FLAG_COMPLEX != 0 {
+        return Ok(None);
+    }
+
+    let value_offset = offset + u16_at(data, offset)? as usize;
+
+    Ok(Some(ResourceValue { data_type: u8_at(data, value_offset + 3)?, data: u32_at(data, value_offset + 4)? }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResourceTable, VALUE_TYPE_BOOLEAN, VALUE_TYPE_STRING};
+
+    // `string/host` has a default value and a French one, declared first. `bool/auto_verify` is `true`.
+    const RESOURCES: &[u8] = include_bytes!("fixtures/resources.arsc");
+
+    #[test]
+    fn prefers_values_of_the_default_configuration() {
+        let table = ResourceTable::parse(RESOURCES).ok().unwrap();
+
+        let host = table.value(0x7f01_0000).unwrap();
+
+        assert_eq!(host.data_type, VALUE_TYPE_STRING);
+        assert_eq!(table.string(host.data), Some("shop.example.com"));
+    }
+
+    #[test]
+    fn decodes_booleans_and_skips_missing_entries() {
+        let table = ResourceTable::parse(RESOURCES).ok().unwrap();
+
+        let auto_verify = table.value(0x7f02_0000).unwrap();
+
+        assert_eq!((auto_verify.data_type, auto_verify.data), (VALUE_TYPE_BOOLEAN, 0xffff_ffff));
+        assert!(table.value(0x7f01_0001).is_none());
+        assert!(table.value(0x7f03_0000).is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        for length in 0..RESOURCES.len() {
+            assert!(ResourceTable::parse(&RESOURCES[..length]).is_err(), "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_chunks() {
+        let mut not_a_table = RESOURCES.to_vec();
+
+        not_a_table[0] = 0x03;
+
+        // The package chunk follows the 12 bytes of the table header and the 80 bytes of the global string pool.
+        let mut package_smaller_than_header = RESOURCES.to_vec();
+
+        package_smaller_than_header[96..100].copy_from_slice(&16u32.to_le_bytes());
+
+        // The first type chunk follows the package header, the type and key string pools, and the first type spec.
+        let mut entries_past_end = RESOURCES.to_vec();
+
+        entries_past_end[528 + 16..528 + 20].copy_from_slice(&0xffffu32.to_le_bytes());
+
+        assert!(ResourceTable::parse(&not_a_table).is_err());
+        assert!(ResourceTable::parse(&package_smaller_than_header).is_err());
+        assert!(ResourceTable::parse(&entries_past_end).is_err());
+    }
+}
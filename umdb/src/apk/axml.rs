@@ -0,0 +1,204 @@
+use super::binary::{chunk_header_at, parse_string_pool, u16_at, u32_at, u8_at, MalformedData, STRING_POOL_CHUNK};
+
+const XML_CHUNK:               u16 = 0x0003;
+const XML_RESOURCE_MAP_CHUNK:  u16 = 0x0180;
+const XML_START_ELEMENT_CHUNK: u16 = 0x0102;
+const XML_END_ELEMENT_CHUNK:   u16 = 0x0103;
+
+const NO_STRING: u32 = 0xffff_ffff;
+
+pub struct XmlAttribute {
+    pub name:        String,
+    pub resource_id: Option<u32>,
+    pub raw_value:   Option<String>,
+    pub data_type:   u8,
+    pub data:        u32,
+}
+
+pub struct XmlElement {
+    pub name:       String,
+    pub attributes: Vec<XmlAttribute>,
+    pub children:   Vec<XmlElement>,
+}
+
+impl XmlElement {
+    // Attribute names are sometimes stripped by obfuscators, in which case only their framework resource id remains.
+    pub fn attribute(&self, name: &str, resource_id: Option<u32>) -> Option<&XmlAttribute> {
+        self
+        .attributes
+        .iter()
+        .find(|attribute| attribute.name == name || (resource_id.is_some() && attribute.resource_id == resource_id))
+    }
+
+    pub fn descendants(&self) -> Vec<&XmlElement> {
+        self
+        .children
+        .iter()
+        .flat_map(|child| std::iter::once(child).chain(child.descendants()))
+        .collect()
+    }
+}
+
+// Decodes Android binary XML, as found in the `AndroidManifest.xml` entry of APKs, into its root element.
+pub fn parse_binary_xml(data: &[u8]) -> Result<XmlElement, MalformedData> {
+    let header = chunk_header_at(data, 0)?;
+
+    if header.chunk_type != XML_CHUNK {
+        return Err(MalformedData);
+    }
+
+    let mut strings       = vec![];
+    let mut resource_ids  = vec![];
+    let mut open_elements = vec![];
+    let mut root          = None;
+    let mut offset        = header.header_size;
+
+    while offset < header.size {
+        let chunk = chunk_header_at(data, offset)?;
+
+        match chunk.chunk_type {
+            STRING_POOL_CHUNK => strings = parse_string_pool(data, offset)?,
+
+            XML_RESOURCE_MAP_CHUNK => {
+                resource_ids = (offset + chunk.header_size..offset + chunk.size)
+                .step_by(4)
+                .map(|id_offset| u32_at(data, id_offset))
+                .collect::<Result<Vec<_>, _>>()?;
+            },
+
+            XML_START_ELEMENT_CHUNK => {
+                open_elements.push(parse_start_element(data, offset + chunk.header_size, &strings, &resource_ids)?);
+            },
+
+            XML_END_ELEMENT_CHUNK => {
+                let element = open_elements.pop().ok_or(MalformedData)?;
+
+                match open_elements.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None         => root = Some(element),
+                }
+            },
+
+            _ => {},
+        }
+
+        offset += chunk.size;
+    }
+
+    root.ok_or(MalformedData)
+}
+
+fn parse_start_element(data: &[u8], offset: usize, strings: &[String], resource_ids: &[u32]) -> Result<XmlElement, MalformedData> {
+    let string = |index: u32| strings.get(index as usize).cloned();
+
+    let name            = string(u32_at(data, offset + 4)?).ok_or(MalformedData)?;
+    let attribute_start = u16_at(data, offset + 8)? as usize;
+    let attribute_size  = u16_at(data, offset + 10)? as usize;
+    let attribute_count = u16_at(data, offset + 12)? as usize;
+
+    let attributes = (0..attribute_count)
+    .map(|index| {
+        let attribute_offset = offset + attribute_start + index * attribute_size;
+
+        let name_index = u32_at(data, attribute_offset + 4)?;
+        let raw_value  = u32_at(data, attribute_offset + 8)?;
+
+        Ok(XmlAttribute {
+            name:        string(name_index).unwrap_or_default(),
+            resource_id: resource_ids.get(name_index as usize).copied(),
+            raw_value:   if raw_value == NO_STRING { None } else { string(raw_value) },
+            data_type:   u8_at(data, attribute_offset + 15)?,
+            data:        u32_at(data, attribute_offset + 16)?,
+        })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(XmlElement { name, attributes, children: vec![] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_binary_xml, XmlElement};
+
+    // The manifest of an app with a browsable activity and a browsable activity alias, laid out as aapt2 writes it.
+    const MANIFEST: &[u8] = include_bytes!("fixtures/AndroidManifest.xml");
+
+    fn names(elements: &[XmlElement]) -> Vec<&str> {
+        elements.iter().map(|element| element.name.as_str()).collect()
+    }
+
+    #[test]
+    fn decodes_the_element_tree() {
+        let manifest = parse_binary_xml(MANIFEST).ok().unwrap();
+
+        assert_eq!(manifest.name, "manifest");
+        assert_eq!(names(&manifest.children), ["application"]);
+        assert_eq!(names(&manifest.children[0].children), ["activity", "activity-alias"]);
+        assert_eq!(names(&manifest.children[0].children[0].children), ["intent-filter", "intent-filter"]);
+        assert_eq!(manifest.descendants().len(), 15);
+    }
+
+    #[test]
+    fn decodes_attributes() {
+        let manifest = parse_binary_xml(MANIFEST).ok().unwrap();
+
+        let package = manifest.attribute("package", None).unwrap();
+
+        assert_eq!(package.raw_value.as_deref(), Some("com.example.shop"));
+        assert_eq!(package.resource_id, None);
+
+        let data = manifest
+        .descendants()
+        .into_iter()
+        .find(|element| element.name == "data")
+        .unwrap();
+
+        // Attributes are found by framework resource id even when their name is not the expected one.
+        let host = data.attribute("obfuscated", Some(0x0101_0028)).unwrap();
+
+        assert_eq!(host.name, "host");
+        assert_eq!(host.raw_value, None);
+        assert_eq!((host.data_type, host.data), (0x01, 0x7f01_0000));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        for length in 0..MANIFEST.len() {
+            assert!(parse_binary_xml(&MANIFEST[..length]).is_err(), "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_chunks() {
+        // The string pool follows the 8 bytes of the XML chunk header.
+        let mut empty_chunk = MANIFEST.to_vec();
+
+        empty_chunk[12..16].copy_from_slice(&0u32.to_le_bytes());
+
+        let mut not_xml = MANIFEST.to_vec();
+
+        not_xml[0] = 0x02;
+
+        let mut string_offset_past_end = MANIFEST.to_vec();
+
+        string_offset_past_end[36..40].copy_from_slice(&0xffffu32.to_le_bytes());
+
+        assert!(parse_binary_xml(&empty_chunk).is_err());
+        assert!(parse_binary_xml(&not_xml).is_err());
+        assert!(parse_binary_xml(&string_offset_past_end).is_err());
+    }
+
+    #[test]
+    fn rejects_unbalanced_elements() {
+        // An XML chunk with no string pool and a single end element.
+        let mut data = vec![];
+
+        data.extend([0x03, 0x00, 0x08, 0x00]);
+        data.extend(32u32.to_le_bytes());
+        data.extend([0x03, 0x01, 0x10, 0x00]);
+        data.extend(24u32.to_le_bytes());
+        data.extend([0; 16]);
+
+        assert!(parse_binary_xml(&data).is_err());
+    }
+}
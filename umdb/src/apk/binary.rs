@@ -0,0 +1,164 @@
+// Readers shared by the binary XML and resource table decoders. Both formats are little-endian sequences of chunks.
+
+pub const STRING_POOL_CHUNK: u16 = 0x0001;
+
+const UTF8_FLAG: u32 = 1 << 8;
+
+pub struct MalformedData;
+
+pub fn u8_at(data: &[u8], offset: usize) -> Result<u8, MalformedData> {
+    data.get(offset).copied().ok_or(MalformedData)
+}
+
+pub fn u16_at(data: &[u8], offset: usize) -> Result<u16, MalformedData> {
+    let bytes = data.get(offset..offset + 2).ok_or(MalformedData)?;
+
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+pub fn u32_at(data: &[u8], offset: usize) -> Result<u32, MalformedData> {
+    let bytes = data.get(offset..offset + 4).ok_or(MalformedData)?;
+
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub struct ChunkHeader {
+    pub chunk_type:  u16,
+    pub header_size: usize,
+    pub size:        usize,
+}
+
+pub fn chunk_header_at(data: &[u8], offset: usize) -> Result<ChunkHeader, MalformedData> {
+    let header = ChunkHeader {
+        chunk_type:  u16_at(data, offset)?,
+        header_size: u16_at(data, offset + 2)? as usize,
+        size:        u32_at(data, offset + 4)? as usize,
+    };
+
+    // A chunk smaller than its header would make chunk iteration loop forever.
+    match header.size >= 8 && header.header_size >= 8 && header.size >= header.header_size && offset + header.size <= data.len() {
+        true  => Ok(header),
+        false => Err(MalformedData),
+    }
+}
+
+pub fn parse_string_pool(data: &[u8], offset: usize) -> Result<Vec<String>, MalformedData> {
+    let header = chunk_header_at(data, offset)?;
+
+    if header.chunk_type != STRING_POOL_CHUNK {
+        return Err(MalformedData);
+    }
+
+    let string_count  = u32_at(data, offset + 8)? as usize;
+    let flags         = u32_at(data, offset + 16)?;
+    let strings_start = offset + u32_at(data, offset + 20)? as usize;
+
+    (0..string_count)
+    .map(|index| {
+        let string_offset = strings_start + u32_at(data, offset + header.header_size + index * 4)? as usize;
+
+        match flags & UTF8_FLAG != 0 {
+            true  => read_utf8_string(data, string_offset),
+            false => read_utf16_string(data, string_offset),
+        }
+    })
+    .collect()
+}
+
+fn read_utf8_string(data: &[u8], offset: usize) -> Result<String, MalformedData> {
+    // The UTF-16 length comes first and is not needed to decode the string.
+    let (_, offset)      = read_utf8_length(data, offset)?;
+    let (length, offset) = read_utf8_length(data, offset)?;
+
+    let bytes = data.get(offset..offset + length).ok_or(MalformedData)?;
+
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn read_utf8_length(data: &[u8], offset: usize) -> Result<(usize, usize), MalformedData> {
+    let first = u8_at(data, offset)? as usize;
+
+    match first & 0x80 != 0 {
+        true  => Ok((((first & 0x7f) << 8) | u8_at(data, offset + 1)? as usize, offset + 2)),
+        false => Ok((first, offset + 1)),
+    }
+}
+
+fn read_utf16_string(data: &[u8], offset: usize) -> Result<String, MalformedData> {
+    let first = u16_at(data, offset)? as usize;
+
+    let (length, offset) = match first & 0x8000 != 0 {
+        true  => (((first & 0x7fff) << 16) | u16_at(data, offset + 2)? as usize, offset + 4),
+        false => (first, offset + 2),
+    };
+
+    let units = (0..length)
+    .map(|index| u16_at(data, offset + index * 2))
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chunk_header_at, parse_string_pool};
+
+    // A string pool chunk holding one string, as aapt2 writes it.
+    fn string_pool(flags: u32, string_data: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+
+        data.extend(1u16.to_le_bytes());
+        data.extend(28u16.to_le_bytes());
+        data.extend((32 + string_data.len() as u32).to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(flags.to_le_bytes());
+        data.extend(32u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(string_data);
+
+        data
+    }
+
+    #[test]
+    fn reads_utf8_strings_with_two_byte_lengths() {
+        let string = "a".repeat(200);
+
+        let mut string_data = vec![0x80, 200, 0x80, 200];
+
+        string_data.extend(string.as_bytes());
+        string_data.push(0);
+
+        let strings = parse_string_pool(&string_pool(1 << 8, &string_data), 0).ok().unwrap();
+
+        assert_eq!(strings, [string]);
+    }
+
+    #[test]
+    fn reads_utf16_strings() {
+        let mut string_data = vec![3, 0];
+
+        string_data.extend("été".encode_utf16().flat_map(u16::to_le_bytes));
+        string_data.extend([0, 0]);
+
+        assert_eq!(parse_string_pool(&string_pool(0, &string_data), 0).ok().unwrap(), ["été"]);
+    }
+
+    #[test]
+    fn rejects_strings_running_past_the_pool() {
+        assert!(parse_string_pool(&string_pool(1 << 8, &[5, 5, b'a']), 0).is_err());
+        assert!(parse_string_pool(&string_pool(0, &[5, 0, b'a', 0]), 0).is_err());
+    }
+
+    #[test]
+    fn rejects_chunks_smaller_than_their_header_or_larger_than_the_data() {
+        let chunk = |header_size: u16, size: u32| [0x01, 0x00].into_iter().chain(header_size.to_le_bytes()).chain(size.to_le_bytes()).collect::<Vec<_>>();
+
+        assert!(chunk_header_at(&chunk(8, 8), 0).is_ok());
+        assert!(chunk_header_at(&chunk(8, 0), 0).is_err());
+        assert!(chunk_header_at(&chunk(16, 8), 0).is_err());
+        assert!(chunk_header_at(&chunk(8, 12), 0).is_err());
+        assert!(chunk_header_at(&chunk(8, 8)[..6], 0).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+use std::{collections::BTreeMap, io::{Read, Seek}};
+
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::core::CatalogLink;
+
+use super::{arsc::{ResourceTable, ResourceValue, VALUE_TYPE_BOOLEAN, VALUE_TYPE_REFERENCE, VALUE_TYPE_STRING}, axml::{parse_binary_xml, XmlElement}};
+
+const MANIFEST_ENTRY:       &str = "AndroidManifest.xml";
+const RESOURCE_TABLE_ENTRY: &str = "resources.arsc";
+
+const VIEW_ACTION:        &str = "android.intent.action.VIEW";
+const BROWSABLE_CATEGORY: &str = "android.intent.category.BROWSABLE";
+
+// Framework resource ids of the attributes read below. Attributes added in recent API levels are only matched by name.
+const NAME_ATTRIBUTE:         Option<u32> = Some(0x0101_0003);
+const SCHEME_ATTRIBUTE:       Option<u32> = Some(0x0101_0027);
+const HOST_ATTRIBUTE:         Option<u32> = Some(0x0101_0028);
+const PORT_ATTRIBUTE:         Option<u32> = Some(0x0101_0029);
+const PATH_ATTRIBUTE:         Option<u32> = Some(0x0101_002a);
+const PATH_PREFIX_ATTRIBUTE:  Option<u32> = Some(0x0101_002b);
+const PATH_PATTERN_ATTRIBUTE: Option<u32> = Some(0x0101_002c);
+const AUTO_VERIFY_ATTRIBUTE:  Option<u32> = Some(0x0101_04ee);
+
+const MAXIMUM_REFERENCE_DEPTH: usize = 8;
+
+#[derive(Debug, Serialize)]
+pub enum ApkManifestError {
+    CannotReadFile(String),
+    CannotWriteFile(String),
+    TooLarge(usize),
+    NotAnArchive(String),
+    CannotReadEntry(String),
+    MalformedResourceTable,
+    MalformedManifest,
+    ManifestMissing,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathMatcher {
+    Path(String),
+    Prefix(String),
+    Suffix(String),
+    Pattern(String),
+    AdvancedPattern(String),
+}
+
+#[derive(Serialize)]
+pub struct BrowsableIntentFilter {
+    pub activity:    String,
+    pub auto_verify: bool,
+    pub schemes:     Vec<String>,
+    pub hosts:       Vec<String>,
+    pub paths:       Vec<PathMatcher>,
+}
+
+#[derive(Serialize)]
+pub struct ManifestLinks {
+    pub package:        Option<String>,
+    pub intent_filters: Vec<BrowsableIntentFilter>,
+
+    // Example links in the format of the link catalog, so they can be saved or launched as they are.
+    pub links: Vec<CatalogLink>,
+}
+
+pub fn read_manifest_links<R: Read + Seek>(apk: R) -> Result<ManifestLinks, ApkManifestError> {
+    let mut archive = ZipArchive
+    ::new(apk)
+    .map_err(|error| ApkManifestError::NotAnArchive(error.to_string()))?;
+
+    let manifest_data = read_entry(&mut archive, MANIFEST_ENTRY)?.ok_or(ApkManifestError::ManifestMissing)?;
+
+    let resources = match read_entry(&mut archive, RESOURCE_TABLE_ENTRY)? {
+        Some(data) => ResourceTable::parse(&data).map_err(|_| ApkManifestError::MalformedResourceTable)?,
+        None       => ResourceTable::default(),
+    };
+
+    let manifest = parse_binary_xml(&manifest_data).map_err(|_| ApkManifestError::MalformedManifest)?;
+
+    let package = manifest
+    .attributes
+    .iter()
+    .find(|attribute| attribute.name == "package")
+    .and_then(|attribute| attribute.raw_value.clone());
+
+    let intent_filters = manifest
+    .descendants()
+    .into_iter()
+    .filter(|element| element.name == "activity" || element.name == "activity-alias")
+    .flat_map(|activity| {
+        let activity_name = attribute_value(activity, "name", NAME_ATTRIBUTE, &resources).unwrap_or_default();
+
+        // Activity names starting with a dot are relative to the package.
+        let activity_name = match (activity_name.starts_with('.'), &package) {
+            (true, Some(package)) => format!("{package}{activity_name}"),
+            _                     => activity_name,
+        };
+
+        activity
+        .children
+        .iter()
+        .filter(|child| child.name == "intent-filter")
+        .filter_map(|filter| read_browsable_filter(&activity_name, filter, &resources))
+        .collect::<Vec<_>>()
+    })
+    .collect::<Vec<_>>();
+
+    let links = intent_filters
+    .iter()
+    .flat_map(|filter| example_links(filter, package.as_deref()))
+    .collect();
+
+    Ok(ManifestLinks { package, intent_filters, links })
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>, ApkManifestError> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry)                                => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(error)                               => return Err(ApkManifestError::CannotReadEntry(error.to_string())),
+    };
+
+    let mut data = vec![];
+
+    entry
+    .read_to_end(&mut data)
+    .map_err(|error| ApkManifestError::CannotReadEntry(error.to_string()))?;
+
+    Ok(Some(data))
+}
+
+fn read_browsable_filter(activity: &str, filter: &XmlElement, resources: &ResourceTable) -> Option<BrowsableIntentFilter> {
+    let names = |element_name: &str| {
+        filter
+        .children
+        .iter()
+        .filter(|child| child.name == element_name)
+        .filter_map(|child| attribute_value(child, "name", NAME_ATTRIBUTE, resources))
+        .collect::<Vec<_>>()
+    };
+
+    if !names("action").iter().any(|action| action == VIEW_ACTION) || !names("category").iter().any(|category| category == BROWSABLE_CATEGORY) {
+        return None;
+    }
+
+    let data_elements = filter
+    .children
+    .iter()
+    .filter(|child| child.name == "data")
+    .collect::<Vec<_>>();
+
+    let values = |name: &str, resource_id: Option<u32>| {
+        data_elements
+        .iter()
+        .filter_map(|data| attribute_value(data, name, resource_id, resources))
+        .collect::<Vec<_>>()
+    };
+
+    let ports = values("port", PORT_ATTRIBUTE);
+
+    // Each port applies to the host declared next to it, which is close enough for example links.
+    let hosts = values("host", HOST_ATTRIBUTE)
+    .into_iter()
+    .enumerate()
+    .map(|(index, host)| match ports.get(index) {
+        Some(port) => format!("{host}:{port}"),
+        None       => host,
+    })
+    .collect();
+
+    let paths = [
+        ("path",                PATH_ATTRIBUTE,         PathMatcher::Path as fn(String) -> PathMatcher),
+        ("pathPrefix",          PATH_PREFIX_ATTRIBUTE,  PathMatcher::Prefix),
+        ("pathSuffix",          None,                   PathMatcher::Suffix),
+        ("pathPattern",         PATH_PATTERN_ATTRIBUTE, PathMatcher::Pattern),
+        ("pathAdvancedPattern", None,                   PathMatcher::AdvancedPattern),
+    ]
+    .into_iter()
+    .flat_map(|(name, resource_id, matcher)| values(name, resource_id).into_iter().map(matcher))
+    .collect();
+
+    Some(BrowsableIntentFilter {
+        paths,
+        hosts,
+
+        activity:    activity.to_string(),
+        schemes:     values("scheme", SCHEME_ATTRIBUTE),
+        auto_verify: attribute_value(filter, "autoVerify", AUTO_VERIFY_ATTRIBUTE, resources).as_deref() == Some("true"),
+    })
+}
+
+fn attribute_value(element: &XmlElement, name: &str, resource_id: Option<u32>, resources: &ResourceTable) -> Option<String> {
+    let attribute = element.attribute(name, resource_id)?;
+
+    if let Some(raw_value) = &attribute.raw_value {
+        return Some(raw_value.clone());
+    }
+
+    resolve_value(ResourceValue { data_type: attribute.data_type, data: attribute.data }, resources, 0)
+}
+
+fn resolve_value(value: ResourceValue, resources: &ResourceTable, depth: usize) -> Option<String> {
+    match value.data_type {
+        VALUE_TYPE_STRING  => resources.string(value.data).map(str::to_string),
+        VALUE_TYPE_BOOLEAN => Some((value.data != 0).to_string()),
+
+        VALUE_TYPE_REFERENCE if depth < MAXIMUM_REFERENCE_DEPTH => resolve_value(resources.value(value.data)?, resources, depth + 1),
+        VALUE_TYPE_REFERENCE                                    => None,
+
+        _ => Some(value.data.to_string()),
+    }
+}
+
+fn example_links(filter: &BrowsableIntentFilter, package: Option<&str>) -> Vec<CatalogLink> {
+    let hosts = match filter.hosts.is_empty() {
+        true  => vec![None],
+        false => filter.hosts.iter().map(|host| Some(host.as_str())).collect(),
+    };
+
+    let paths = match filter.paths.is_empty() {
+        true  => vec![String::new()],
+        false => filter.paths.iter().map(example_path).collect(),
+    };
+
+    let mut tags = vec!["imported".to_string()];
+
+    if filter.auto_verify {
+        tags.push("auto-verify".to_string());
+    }
+
+    let activity_name = filter.activity.rsplit('.').next().unwrap_or(&filter.activity);
+
+    filter
+    .schemes
+    .iter()
+    .flat_map(|scheme| hosts.iter().map(move |host| (scheme, host)))
+    .flat_map(|(scheme, host)| paths.iter().map(move |path| (scheme, host, path)))
+    .map(|(scheme, host, path)| {
+        let url = match host {
+            Some(host) => format!("{scheme}://{}{path}", host.replace('*', "example")),
+            None       => format!("{scheme}:{path}"),
+        };
+
+        CatalogLink {
+            name:       format!("{activity_name} {url}"),
+            tags:       tags.clone(),
+            package:    package.map(str::to_string),
+            extras:     BTreeMap::new(),
+            parameters: BTreeMap::new(),
+
+            url,
+        }
+    })
+    .collect()
+}
+
+// Turns a path matcher into one path it matches, replacing wildcards with placeholder text.
+fn example_path(matcher: &PathMatcher) -> String {
+    let path = match matcher {
+        PathMatcher::Path(path) | PathMatcher::Prefix(path) => path.clone(),
+
+        PathMatcher::Suffix(suffix) => format!("/example{suffix}"),
+
+        PathMatcher::Pattern(pattern) | PathMatcher::AdvancedPattern(pattern) => pattern
+        .replace("\\\\", "\\")
+        .replace(".*", "example")
+        .replace(".+", "example")
+        .replace("[^/]*", "example")
+        .replace("[^/]+", "example")
+        .replace("\\.", ".")
+        .replace(['*', '+', '?'], ""),
+    };
+
+    match path.starts_with('/') || path.is_empty() {
+        true  => path,
+        false => format!("/{path}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::{read_manifest_links, ApkManifestError, PathMatcher};
+
+    const MANIFEST:  &[u8] = include_bytes!("fixtures/AndroidManifest.xml");
+    const RESOURCES: &[u8] = include_bytes!("fixtures/resources.arsc");
+
+    fn apk(entries: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+
+        for (name, data) in entries {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+
+        let mut apk = writer.finish().unwrap();
+
+        apk.set_position(0);
+
+        apk
+    }
+
+    #[test]
+    fn reads_browsable_intent_filters() {
+        let links = read_manifest_links(apk(&[("AndroidManifest.xml", MANIFEST), ("resources.arsc", RESOURCES)])).unwrap();
+
+        assert_eq!(links.package.as_deref(), Some("com.example.shop"));
+        assert_eq!(links.intent_filters.len(), 2);
+
+        let orders = &links.intent_filters[0];
+
+        assert_eq!(orders.activity, "com.example.shop.OrderActivity");
+        assert!(orders.auto_verify);
+        assert_eq!(orders.schemes, ["https"]);
+        assert_eq!(orders.hosts, ["shop.example.com"]);
+        assert!(matches!(&orders.paths[..], [PathMatcher::Prefix(prefix)] if prefix == "/orders"));
+
+        let legacy_orders = &links.intent_filters[1];
+
+        assert_eq!(legacy_orders.activity, "com.example.shop.LegacyOrders");
+        assert!(!legacy_orders.auto_verify);
+        assert!(legacy_orders.hosts.is_empty());
+
+        let urls = links
+        .links
+        .iter()
+        .map(|link| link.url.as_str())
+        .collect::<Vec<_>>();
+
+        assert_eq!(urls, ["https://shop.example.com/orders", "shop:/order/example"]);
+        assert_eq!(links.links[0].tags, ["imported", "auto-verify"]);
+        assert_eq!(links.links[0].package.as_deref(), Some("com.example.shop"));
+    }
+
+    #[test]
+    fn leaves_references_unresolved_without_resource_table() {
+        let links = read_manifest_links(apk(&[("AndroidManifest.xml", MANIFEST)])).unwrap();
+
+        assert!(links.intent_filters[0].hosts.is_empty());
+        assert!(!links.intent_filters[0].auto_verify);
+    }
+
+    #[test]
+    fn reports_unreadable_apks() {
+        let not_an_archive     = read_manifest_links(Cursor::new(MANIFEST));
+        let manifest_missing   = read_manifest_links(apk(&[("resources.arsc", RESOURCES)]));
+        let malformed_manifest = read_manifest_links(apk(&[("AndroidManifest.xml", &MANIFEST[..100])]));
+        let malformed_table    = read_manifest_links(apk(&[("AndroidManifest.xml", MANIFEST), ("resources.arsc", &RESOURCES[..100])]));
+
+        assert!(matches!(not_an_archive, Err(ApkManifestError::NotAnArchive(_))));
+        assert!(matches!(manifest_missing, Err(ApkManifestError::ManifestMissing)));
+        assert!(matches!(malformed_manifest, Err(ApkManifestError::MalformedManifest)));
+        assert!(matches!(malformed_table, Err(ApkManifestError::MalformedResourceTable)));
+    }
+}
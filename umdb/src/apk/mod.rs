@@ -0,0 +1,5 @@
+mod axml;
+mod arsc;
+mod binary;
+
+pub mod manifest;
@@ -0,0 +1,166 @@
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use crate::{adb::flow::adb_run_flow_on_devices, common::{fan_out::{DeviceSelector, DeviceTargets}, flow::{parse_flow, render_junit_report}}, core::Configuration};
+
+const USAGE: &str = "\
+Usage: umdb flow run <flow file>... [options]
+
+Options:
+  --device <id>            Device to run the flows on. Can be repeated.
+  --select <selector>      all_online (default), all_remote, label=<label> or model=<regex>.
+  --concurrency <count>    Maximum number of devices worked on at the same time.
+  --adb <path>             adb executable, `adb` by default.
+  --data-directory <path>  Directory holding the device profiles.
+  --artifacts <path>       Directory screenshots are saved to.
+  --json <path>            Writes the results as JSON to a file instead of the standard output.
+  --junit <path>           Writes the results as a JUnit XML report.";
+
+struct FlowRunArguments {
+    flow_paths: Vec<PathBuf>,
+    targets: DeviceTargets,
+    concurrency: Option<usize>,
+    json_path: Option<PathBuf>,
+    junit_path: Option<PathBuf>,
+    configuration: Configuration,
+}
+
+// Exits with 1 when a flow fails, and 2 when flows cannot be run at all.
+pub async fn run(arguments: Vec<String>) -> ExitCode {
+    let arguments = match arguments.first().map(String::as_str) {
+        Some("flow") if arguments.get(1).map(String::as_str) == Some("run") => parse_flow_run_arguments(&arguments[2..]),
+
+        _ => Err(String::new()),
+    };
+
+    let arguments = match arguments {
+        Ok(arguments) => arguments,
+
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("{error}\n");
+            }
+
+            eprintln!("{USAGE}");
+
+            return ExitCode::from(2);
+        },
+    };
+
+    match run_flows(arguments).await {
+        Ok(true)  => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+
+        Err(error) => {
+            eprintln!("{error}");
+
+            ExitCode::from(2)
+        },
+    }
+}
+
+// Returns whether all flows passed on all devices.
+async fn run_flows(arguments: FlowRunArguments) -> Result<bool, String> {
+    let flows = arguments
+    .flow_paths
+    .iter()
+    .map(|path| {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Cannot read {}: {error}", path.display()))?;
+
+        parse_flow(&contents).map_err(|error| format!("Cannot parse {}: {}", path.display(), serde_json::to_string(&error).unwrap()))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = vec![];
+
+    for flow in &flows {
+        let flow_results = adb_run_flow_on_devices(&arguments.configuration, flow, &arguments.targets, arguments.concurrency)
+        .await
+        .map_err(|error| format!("Cannot find the devices to run flows on: {}", serde_json::to_string(&error).unwrap()))?;
+
+        for result in &flow_results {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+
+            eprintln!("{status} {} on {} ({} ms)", result.flow, result.device_id, result.duration_ms);
+        }
+
+        results.extend(flow_results);
+    }
+
+    let json = serde_json::to_string_pretty(&results).unwrap();
+
+    match &arguments.json_path {
+        Some(path) => fs::write(path, json + "\n").map_err(|error| format!("Cannot write {}: {error}", path.display()))?,
+        None       => println!("{json}"),
+    }
+
+    if let Some(path) = &arguments.junit_path {
+        fs::write(path, render_junit_report(&results)).map_err(|error| format!("Cannot write {}: {error}", path.display()))?;
+    }
+
+    Ok(results.iter().all(|result| result.passed))
+}
+
+fn parse_flow_run_arguments(arguments: &[String]) -> Result<FlowRunArguments, String> {
+    let mut parsed = FlowRunArguments {
+        flow_paths:    vec![],
+        targets:       DeviceTargets::Selector(DeviceSelector::AllOnline),
+        concurrency:   None,
+        json_path:     None,
+        junit_path:    None,
+        configuration: Configuration::new(),
+    };
+
+    let mut device_ids = vec![];
+    let mut arguments  = arguments.iter();
+
+    parsed.configuration.adb_command = Some("adb".to_string());
+
+    while let Some(argument) = arguments.next() {
+        if !argument.starts_with("--") {
+            parsed.flow_paths.push(PathBuf::from(argument));
+
+            continue;
+        }
+
+        let value = arguments
+        .next()
+        .ok_or_else(|| format!("Missing value for {argument}"))?;
+
+        match argument.as_str() {
+            "--device"         => device_ids.push(value.clone()),
+            "--select"         => parsed.targets = DeviceTargets::Selector(parse_selector(value)?),
+            "--adb"            => parsed.configuration.adb_command = Some(value.clone()),
+            "--data-directory" => parsed.configuration.data_directory = Some(PathBuf::from(value)),
+            "--artifacts"      => parsed.configuration.artifacts_directory = Some(PathBuf::from(value)),
+            "--json"           => parsed.json_path = Some(PathBuf::from(value)),
+            "--junit"          => parsed.junit_path = Some(PathBuf::from(value)),
+
+            "--concurrency" => parsed.concurrency = Some(value.parse().map_err(|_| format!("Invalid concurrency: {value}"))?),
+
+            _ => return Err(format!("Unknown option: {argument}")),
+        }
+    }
+
+    if parsed.flow_paths.is_empty() {
+        return Err("No flow file given".to_string());
+    }
+
+    if !device_ids.is_empty() {
+        parsed.targets = DeviceTargets::Ids(device_ids);
+    }
+
+    Ok(parsed)
+}
+
+fn parse_selector(selector: &str) -> Result<DeviceSelector, String> {
+    match selector.split_once('=') {
+        Some(("label", label)) => Ok(DeviceSelector::Label(label.to_string())),
+        Some(("model", regex)) => Ok(DeviceSelector::ModelRegex(regex.to_string())),
+
+        _ => match selector {
+            "all_online" => Ok(DeviceSelector::AllOnline),
+            "all_remote" => Ok(DeviceSelector::AllRemote),
+            _            => Err(format!("Unknown selector: {selector}")),
+        },
+    }
+}
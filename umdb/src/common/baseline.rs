@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+// The state of a device that tests depend on. Settings that are not set are `null`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub timestamp: u64,
+    pub device_id: String,
+
+    // Namespace (`global`, `secure` or `system`), then key.
+    pub settings: BTreeMap<String, BTreeMap<String, Option<String>>>,
+
+    // Display overrides, as `<width>x<height>` and dots per inch. `null` when not overridden.
+    pub display_size: Option<String>,
+    pub display_density: Option<u32>,
+
+    pub locale: String,
+
+    // Version codes of the apps installed by users.
+    pub packages: BTreeMap<String, u64>,
+}
+
+// Expected values are those of the baseline.
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BaselineDifference {
+    Setting { namespace: String, key: String, expected: Option<String>, actual: Option<String> },
+    DisplaySize { expected: Option<String>, actual: Option<String> },
+    DisplayDensity { expected: Option<u32>, actual: Option<u32> },
+    Locale { expected: String, actual: String },
+    Package { package: String, expected: Option<u64>, actual: Option<u64> },
+}
+
+#[derive(Serialize)]
+pub struct UnrestoredDifference {
+    pub difference: BaselineDifference,
+    pub reason: String,
+}
+
+#[derive(Default, Serialize)]
+pub struct BaselineRestoration {
+    pub restored: Vec<BaselineDifference>,
+    pub unrestored: Vec<UnrestoredDifference>,
+}
+
+impl Baseline {
+    pub fn diff(&self, current: &Baseline) -> Vec<BaselineDifference> {
+        let mut differences = vec![];
+
+        for (namespace, settings) in &self.settings {
+            for (key, expected) in settings {
+                let actual = current
+                .settings
+                .get(namespace)
+                .and_then(|settings| settings.get(key))
+                .cloned()
+                .flatten();
+
+                if *expected != actual {
+                    differences.push(BaselineDifference::Setting {
+                        namespace: namespace.clone(),
+                        key:       key.clone(),
+                        expected:  expected.clone(),
+                        actual,
+                    });
+                }
+            }
+        }
+
+        if self.display_size != current.display_size {
+            differences.push(BaselineDifference::DisplaySize { expected: self.display_size.clone(), actual: current.display_size.clone() });
+        }
+
+        if self.display_density != current.display_density {
+            differences.push(BaselineDifference::DisplayDensity { expected: self.display_density, actual: current.display_density });
+        }
+
+        if self.locale != current.locale {
+            differences.push(BaselineDifference::Locale { expected: self.locale.clone(), actual: current.locale.clone() });
+        }
+
+        let packages = self
+        .packages
+        .keys()
+        .chain(current.packages.keys())
+        .collect::<BTreeSet<_>>();
+
+        for package in packages {
+            let expected = self.packages.get(package).copied();
+            let actual   = current.packages.get(package).copied();
+
+            if expected != actual {
+                differences.push(BaselineDifference::Package { package: package.clone(), expected, actual });
+            }
+        }
+
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Baseline, BaselineDifference};
+
+    fn baseline(settings: &[(&str, &str, Option<&str>)], packages: &[(&str, u64)]) -> Baseline {
+        let mut namespaces = BTreeMap::<String, BTreeMap<String, Option<String>>>::new();
+
+        for (namespace, key, value) in settings {
+            namespaces
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value.map(str::to_string));
+        }
+
+        Baseline {
+            timestamp:       1_760_000_000_000,
+            device_id:       "emulator-5554".to_string(),
+            settings:        namespaces,
+            display_size:    None,
+            display_density: None,
+            locale:          "en-US".to_string(),
+            packages:        packages.iter().map(|(package, version_code)| (package.to_string(), *version_code)).collect(),
+        }
+    }
+
+    #[test]
+    fn has_no_differences_with_itself() {
+        let captured = baseline(&[("global", "http_proxy", None), ("system", "font_scale", Some("1.0"))], &[("com.example", 42)]);
+
+        assert!(captured.diff(&captured.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_settings_set_since_the_capture() {
+        let captured = baseline(&[("global", "http_proxy", None), ("system", "font_scale", Some("1.0"))], &[]);
+        let current  = baseline(&[("global", "http_proxy", Some("10.0.2.2:8888")), ("system", "font_scale", Some("1.0"))], &[]);
+
+        let differences = captured.diff(&current);
+
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(&differences[0], BaselineDifference::Setting { namespace, key, expected: None, actual: Some(actual) } if namespace == "global" && key == "http_proxy" && actual == "10.0.2.2:8888"));
+
+        // The other way around, the setting has to be removed.
+        assert!(matches!(&current.diff(&captured)[0], BaselineDifference::Setting { expected: Some(_), actual: None, .. }));
+    }
+
+    #[test]
+    fn reports_package_changes() {
+        let captured = baseline(&[], &[("com.example", 42), ("com.example.legacy", 7)]);
+        let current  = baseline(&[], &[("com.example", 43), ("com.example.debug", 1)]);
+
+        let differences = captured
+        .diff(&current)
+        .into_iter()
+        .map(|difference| match difference {
+            BaselineDifference::Package { package, expected, actual } => (package, expected, actual),
+            _                                                        => panic!("Only packages differ"),
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(differences, [
+            ("com.example".to_string(), Some(42), Some(43)),
+            ("com.example.debug".to_string(), None, Some(1)),
+            ("com.example.legacy".to_string(), Some(7), None),
+        ]);
+    }
+
+    #[test]
+    fn reports_display_overrides() {
+        let captured = baseline(&[], &[]);
+
+        let current = Baseline {
+            display_size:    Some("720x1600".to_string()),
+            display_density: Some(560),
+            ..captured.clone()
+        };
+
+        let differences = captured.diff(&current);
+
+        assert_eq!(differences.len(), 2);
+        assert!(matches!(&differences[0], BaselineDifference::DisplaySize { expected: None, actual: Some(size) } if size == "720x1600"));
+        assert!(matches!(differences[1], BaselineDifference::DisplayDensity { expected: None, actual: Some(560) }));
+    }
+
+    #[test]
+    fn reports_locale_drift() {
+        let captured = baseline(&[], &[]);
+        let current  = Baseline { locale: "fr-FR,en-US".to_string(), ..captured.clone() };
+
+        let differences = captured.diff(&current);
+
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(&differences[0], BaselineDifference::Locale { expected, actual } if expected == "en-US" && actual == "fr-FR,en-US"));
+    }
+}
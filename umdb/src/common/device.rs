@@ -1,6 +1,6 @@
 use std::net::IpAddr;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
 pub enum DeviceListingError {
@@ -11,12 +11,146 @@ pub enum DeviceListingError {
     DeviceUnresponsive,
 }
 
+// Only devices in the `Device` state accept commands.
+#[derive(Clone, PartialEq, Serialize)]
+pub enum DeviceState {
+    Device,
+    Offline,
+    Unauthorized,
+    Authorizing,
+    Connecting,
+    NoPermissions,
+    Recovery,
+    Sideload,
+    Bootloader,
+    Rescue,
+    Host,
+    Unknown(String),
+}
+
+// Set by users to tell look-alike devices apart.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    #[serde(default)] pub nickname: Option<String>,
+    #[serde(default)] pub labels:   Vec<String>,
+    #[serde(default)] pub owner:    Option<String>,
+    #[serde(default)] pub notes:    Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct DeviceListingOptions {
+    #[serde(default)] pub include_link_local: bool,
+
+    // Devices are otherwise only asked for their properties when remote transports have to be merged, and never for
+    // their network addresses.
+    #[serde(default)] pub query_devices: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct NetworkAddress {
+    pub interface: Option<String>,
+    pub address: IpAddr,
+    pub prefix_length: Option<u8>,
+    pub is_link_local: bool,
+
+    // Whether `adb connect` can be expected to reach the device at this address from the host.
+    pub is_connectable: bool,
+}
+
+// One way adb reaches a device: USB, TCP or mDNS.
+#[derive(Serialize)]
+pub struct Transport {
+    pub id: String,
+    pub serial: String,
+    pub state: DeviceState,
+    pub is_remote: bool,
+    pub is_offline: bool,
+    pub transport_id: Option<u64>,
+}
+
+// The bundled catalog is a stub of a few dozen phones, not the Google Play supported devices list: without a configured
+// catalog, a missing name does not mean that the device has none.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MarketingName {
+    Found { name: String },
+    NotInCatalog,
+
+    // Neither adb nor the device reported a model to look up.
+    UnknownModel,
+}
+
+// A physical device. Its id, state and flags are those of the transport operations go through.
 #[derive(Serialize)]
 pub struct Device {
     pub id: String,
+    pub serial: String,
+    pub state: DeviceState,
     pub is_remote: bool,
     pub is_offline: bool,
+
+    // Manufacturer and model the device reports, such as "samsung SM-S911B", when it was queried.
     pub model: Option<String>,
-    pub alias: Option<String>,
+
+    // Model `adb devices -l` reports, such as "SM_S911B".
+    pub listed_model: Option<String>,
+
+    // Name the device is sold under, such as "Galaxy S23" for a SM-S911B.
+    pub marketing_name: MarketingName,
+
+    pub profile: Option<DeviceProfile>,
+    pub product: Option<String>,
+    pub device: Option<String>,
+    pub transport_id: Option<u64>,
     pub known_ips: Vec<IpAddr>,
+    pub network_addresses: Vec<NetworkAddress>,
+    pub transports: Vec<Transport>,
+}
+
+impl Device {
+    // Devices without a profile have no label.
+    pub fn has_label(&self, label: &str) -> bool {
+        self
+        .profile
+        .as_ref()
+        .is_some_and(|profile| profile.labels.iter().any(|other| other == label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Device, DeviceProfile, DeviceState, MarketingName};
+
+    fn device(profile: Option<DeviceProfile>) -> Device {
+        Device {
+            id:                "R58M12ABCDE".to_string(),
+            serial:            "R58M12ABCDE".to_string(),
+            state:             DeviceState::Device,
+            is_remote:         false,
+            is_offline:        false,
+            model:             None,
+            listed_model:      None,
+            marketing_name:    MarketingName::UnknownModel,
+            product:           None,
+            device:            None,
+            transport_id:      None,
+            known_ips:         vec![],
+            network_addresses: vec![],
+            transports:        vec![],
+
+            profile,
+        }
+    }
+
+    #[test]
+    fn matches_labels_exactly() {
+        let profile = DeviceProfile { labels: vec!["smoke".to_string(), "android-14".to_string()], ..DeviceProfile::default() };
+
+        assert!(device(Some(profile.clone())).has_label("smoke"));
+        assert!(device(Some(profile.clone())).has_label("android-14"));
+        assert!(!device(Some(profile)).has_label("android"));
+
+        assert!(!device(Some(DeviceProfile::default())).has_label("smoke"));
+        assert!(!device(None).has_label("smoke"));
+    }
 }
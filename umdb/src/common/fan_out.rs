@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, future::Future};
+
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::device::DeviceListingError;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceSelector {
+    AllOnline,
+    AllRemote,
+    ModelRegex(String),
+    Label(String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceTargets {
+    Ids(Vec<String>),
+    Selector(DeviceSelector),
+}
+
+#[derive(Serialize)]
+pub enum ResolveTargetsError {
+    DeviceListingFailed(DeviceListingError),
+    InvalidModelRegex(String),
+    NoMatchingDevice,
+}
+
+// Runs the operation on every device, with at most `concurrency` devices being worked on at the same time.
+pub async fn fan_out<Operation, OperationFuture, T>(device_ids: Vec<String>, concurrency: usize, operation: Operation) -> BTreeMap<String, T>
+where
+    Operation: Fn(String) -> OperationFuture,
+    OperationFuture: Future<Output = T>,
+{
+    stream
+    ::iter(device_ids)
+    .map(|device_id| {
+        let task = operation(device_id.clone());
+
+        async move { (device_id, task.await) }
+    })
+    .buffer_unordered(concurrency.max(1))
+    .collect()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::{AtomicUsize, Ordering}, time::Duration};
+
+    use super::fan_out;
+
+    #[tokio::test]
+    async fn runs_at_most_concurrency_operations_at_once() {
+        let device_ids = (0..10).map(|index| format!("emulator-{}", 5554 + 2 * index)).collect::<Vec<_>>();
+
+        let running         = AtomicUsize::new(0);
+        let maximum_running = AtomicUsize::new(0);
+
+        let results = fan_out(device_ids.clone(), 3, |device_id| {
+            let running         = &running;
+            let maximum_running = &maximum_running;
+
+            async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+
+                maximum_running.fetch_max(now_running, Ordering::SeqCst);
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+
+                running.fetch_sub(1, Ordering::SeqCst);
+
+                device_id.len()
+            }
+        })
+        .await;
+
+        assert_eq!(maximum_running.load(Ordering::SeqCst), 3);
+        assert!(results.into_keys().eq(device_ids));
+    }
+
+    #[tokio::test]
+    async fn runs_operations_one_by_one_without_concurrency() {
+        let results = fan_out(vec!["R58M12ABCDE".to_string(), "emulator-5554".to_string()], 0, |device_id| async move { device_id.len() }).await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["emulator-5554"], 13);
+    }
+}
@@ -0,0 +1,319 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::ui::UiSelector;
+
+#[derive(Serialize)]
+pub enum FlowError {
+    MalformedFlow(String),
+    EmptyFlow,
+}
+
+// A smoke test written in YAML:
+//
+// name: Cart link
+// steps:
+//   - open_link: { url: "example://cart" }
+//   - wait_for_activity: { activity: .CartActivity }
+//   - tap: { selector: { resource_id: skip } }
+//   - screenshot: { name: cart }
+//   - assert_log_not_contains: { pattern: FATAL EXCEPTION }
+#[derive(Deserialize)]
+pub struct Flow {
+    pub name: String,
+    pub steps: Vec<FlowStep>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowStep {
+    OpenLink {
+        url: String,
+        package: Option<String>,
+        extras: Option<BTreeMap<String, String>>,
+    },
+
+    WaitForActivity {
+        activity: String,
+        timeout_ms: Option<u64>,
+    },
+
+    AssertActivity {
+        activity: String,
+    },
+
+    AssertElement {
+        selector: UiSelector,
+        timeout_ms: Option<u64>,
+    },
+
+    Tap {
+        selector: UiSelector,
+        timeout_ms: Option<u64>,
+    },
+
+    // Replaces the text of the selected field, or types into the focused one.
+    Type {
+        selector: Option<UiSelector>,
+        text: String,
+    },
+
+    PressKey {
+        key: String,
+    },
+
+    Screenshot {
+        name: Option<String>,
+    },
+
+    // Only looks at what was logged since the flow started.
+    AssertLogNotContains {
+        pattern: String,
+    },
+
+    Sleep {
+        duration_ms: u64,
+    },
+}
+
+impl FlowStep {
+    pub fn describe(&self) -> String {
+        match self {
+            FlowStep::OpenLink { url, .. }             => format!("open link {url}"),
+            FlowStep::WaitForActivity { activity, .. } => format!("wait for activity {activity}"),
+            FlowStep::AssertActivity { activity }      => format!("assert activity {activity}"),
+            FlowStep::AssertElement { selector, .. }   => format!("assert element {}", selector.describe()),
+            FlowStep::Tap { selector, .. }             => format!("tap {}", selector.describe()),
+            FlowStep::Type { text, .. }                => format!("type {text:?}"),
+            FlowStep::PressKey { key }                 => format!("press key {key}"),
+            FlowStep::Screenshot { name }              => format!("screenshot {}", name.as_deref().unwrap_or_default()).trim_end().to_string(),
+            FlowStep::AssertLogNotContains { pattern } => format!("assert log does not contain {pattern:?}"),
+            FlowStep::Sleep { duration_ms }            => format!("sleep {duration_ms} ms"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Serialize)]
+pub struct StepResult {
+    pub name: String,
+    pub status: StepStatus,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+
+    // Taken by screenshot steps, and when a step fails.
+    pub screenshot: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+pub struct FlowResult {
+    pub flow: String,
+    pub device_id: String,
+    pub passed: bool,
+    pub timestamp: u64,
+    pub duration_ms: u64,
+    pub steps: Vec<StepResult>,
+}
+
+pub fn parse_flow(contents: &str) -> Result<Flow, FlowError> {
+    // serde_norway expects steps written with YAML tags (`!tap`). Going through JSON lets them be written as maps instead.
+    let value = serde_norway::from_str::<serde_json::Value>(contents).map_err(|error| FlowError::MalformedFlow(error.to_string()))?;
+
+    let flow = serde_json::from_value::<Flow>(value).map_err(|error| FlowError::MalformedFlow(error.to_string()))?;
+
+    match flow.steps.is_empty() {
+        false => Ok(flow),
+        true  => Err(FlowError::EmptyFlow),
+    }
+}
+
+// One test suite per flow and device, with one test case per step. Screenshots are attached the way Jenkins and GitLab
+// pick them up.
+pub fn render_junit_report(results: &[FlowResult]) -> String {
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    let count_steps = |status: StepStatus| results
+    .iter()
+    .flat_map(|result| &result.steps)
+    .filter(|step| step.status == status)
+    .count();
+
+    report += &format!(
+        "<testsuites name=\"umdb\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+        results.iter().map(|result| result.steps.len()).sum::<usize>(),
+        count_steps(StepStatus::Failed),
+        count_steps(StepStatus::Skipped),
+        seconds(results.iter().map(|result| result.duration_ms).sum()),
+    );
+
+    for result in results {
+        let count_suite_steps = |status: StepStatus| result.steps.iter().filter(|step| step.status == status).count();
+
+        report += &format!(
+            "  <testsuite name=\"{}\" hostname=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\">\n",
+            escape_xml(&format!("{} on {}", result.flow, result.device_id)),
+            escape_xml(&result.device_id),
+            result.steps.len(),
+            count_suite_steps(StepStatus::Failed),
+            count_suite_steps(StepStatus::Skipped),
+            seconds(result.duration_ms),
+        );
+
+        for (index, step) in result.steps.iter().enumerate() {
+            report += &format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n",
+                escape_xml(&result.flow),
+                escape_xml(&format!("{}. {}", index + 1, step.name)),
+                seconds(step.duration_ms),
+            );
+
+            match step.status {
+                StepStatus::Passed  => {},
+                StepStatus::Skipped => report += "      <skipped/>\n",
+
+                StepStatus::Failed => {
+                    let error = escape_xml(step.error.as_deref().unwrap_or_default());
+
+                    report += &format!("      <failure message=\"{error}\">{error}</failure>\n");
+                },
+            }
+
+            if let Some(screenshot) = &step.screenshot {
+                report += &format!("      <system-out>[[ATTACHMENT|{}]]</system-out>\n", escape_xml(&screenshot.to_string_lossy()));
+            }
+
+            report += "    </testcase>\n";
+        }
+
+        report += "  </testsuite>\n";
+    }
+
+    report + "</testsuites>\n"
+}
+
+fn seconds(duration_ms: u64) -> String {
+    format!("{:.3}", duration_ms as f64 / 1000.0)
+}
+
+fn escape_xml(text: &str) -> String {
+    text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_flow, render_junit_report, FlowError, FlowResult, FlowStep, StepResult, StepStatus};
+
+    // The flow our link verification scripts run on the cart link.
+    const CART_FLOW: &str = r#"
+name: Cart link
+steps:
+  - open_link: { url: "example://cart?item=42", package: com.example }
+  - wait_for_activity: { activity: .CartActivity, timeout_ms: 10000 }
+  - tap:
+      selector:
+        resource_id: skip
+        instance: 1
+  - type: { text: "Ünïcode & more" }
+  - screenshot: {}
+  - assert_log_not_contains: { pattern: FATAL EXCEPTION }
+  - sleep: { duration_ms: 500 }
+"#;
+
+    fn step(name: &str, status: StepStatus, error: Option<&str>, screenshot: Option<&str>) -> StepResult {
+        StepResult {
+            name:        name.to_string(),
+            duration_ms: 1250,
+            error:       error.map(str::to_string),
+            screenshot:  screenshot.map(PathBuf::from),
+            status,
+        }
+    }
+
+    #[test]
+    fn parses_flow() {
+        let flow = parse_flow(CART_FLOW).ok().unwrap();
+
+        assert_eq!(flow.name, "Cart link");
+        assert_eq!(flow.steps.len(), 7);
+
+        assert!(matches!(&flow.steps[0], FlowStep::OpenLink { url, package: Some(package), extras: None } if url == "example://cart?item=42" && package == "com.example"));
+        assert!(matches!(&flow.steps[1], FlowStep::WaitForActivity { activity, timeout_ms: Some(10000) } if activity == ".CartActivity"));
+        assert!(matches!(&flow.steps[2], FlowStep::Tap { selector, timeout_ms: None } if selector.resource_id.as_deref() == Some("skip") && selector.instance == Some(1)));
+        assert!(matches!(&flow.steps[3], FlowStep::Type { selector: None, text } if text == "Ünïcode & more"));
+
+        let descriptions = flow.steps.iter().map(FlowStep::describe).collect::<Vec<_>>();
+
+        assert_eq!(descriptions[2], "tap resource_id=\"skip\" instance=\"1\"");
+        assert_eq!(descriptions[4], "screenshot");
+        assert_eq!(descriptions[6], "sleep 500 ms");
+    }
+
+    #[test]
+    fn parses_json_flow() {
+        let flow = parse_flow(r#"{"name": "Home", "steps": [{"press_key": {"key": "HOME"}}]}"#).ok().unwrap();
+
+        assert!(matches!(&flow.steps[0], FlowStep::PressKey { key } if key == "HOME"));
+    }
+
+    #[test]
+    fn rejects_invalid_flows() {
+        assert!(matches!(parse_flow("name: Nothing\nsteps: []\n"), Err(FlowError::EmptyFlow)));
+        assert!(matches!(parse_flow("name: Unknown\nsteps:\n  - swipe: {}\n"), Err(FlowError::MalformedFlow(_))));
+        assert!(matches!(parse_flow("steps:\n  - sleep: { duration_ms: 1 }\n"), Err(FlowError::MalformedFlow(_))));
+        assert!(matches!(parse_flow("name: [unclosed"), Err(FlowError::MalformedFlow(_))));
+    }
+
+    #[test]
+    fn renders_junit_report() {
+        let results = [
+            FlowResult {
+                flow:        "Cart <link>".to_string(),
+                device_id:   "emulator-5554".to_string(),
+                passed:      false,
+                timestamp:   1_760_000_000_000,
+                duration_ms: 3750,
+                steps:       vec![
+                    step("open link example://cart", StepStatus::Passed, None, None),
+                    step("wait for activity .CartActivity", StepStatus::Failed, Some("The foreground activity is com.example/.HomeActivity"), Some("/tmp/flows/cart & co.png")),
+                    step("tap resource_id=\"skip\"", StepStatus::Skipped, None, None),
+                ],
+            },
+        ];
+
+        let report = render_junit_report(&results);
+
+        assert_eq!(report, "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites name=\"umdb\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"3.750\">
+  <testsuite name=\"Cart &lt;link&gt; on emulator-5554\" hostname=\"emulator-5554\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"3.750\">
+    <testcase classname=\"Cart &lt;link&gt;\" name=\"1. open link example://cart\" time=\"1.250\">
+    </testcase>
+    <testcase classname=\"Cart &lt;link&gt;\" name=\"2. wait for activity .CartActivity\" time=\"1.250\">
+      <failure message=\"The foreground activity is com.example/.HomeActivity\">The foreground activity is com.example/.HomeActivity</failure>
+      <system-out>[[ATTACHMENT|/tmp/flows/cart &amp; co.png]]</system-out>
+    </testcase>
+    <testcase classname=\"Cart &lt;link&gt;\" name=\"3. tap resource_id=&quot;skip&quot;\" time=\"1.250\">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+");
+
+        assert!(roxmltree::Document::parse(&report).is_ok());
+    }
+}
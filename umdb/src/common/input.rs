@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub enum InputError {
+    CannotRunProcess(String),
+    CommandFailed(String),
+    DebugBridgePathMissing,
+    DeviceUnresponsive,
+    EmptyKeyCombination,
+    InvalidKey(String),
+    UnicodeKeyboardMissing,
+
+    // ADBKeyBoard was selected, but the system did not bind it in time.
+    UnicodeKeyboardNotReady,
+}
+
+// The device an event pretends to come from. Apps may react differently to a tap from a touchscreen and from a mouse.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputSource {
+    Touchscreen,
+    Touchpad,
+    Touchnavigation,
+    Stylus,
+    Mouse,
+    Trackball,
+    Dpad,
+    Keyboard,
+    Gamepad,
+    Joystick,
+}
+
+// Keys are named after Android key codes, with or without their `KEYCODE_` prefix: `BACK`, `DPAD_UP`, `MEDIA_PLAY_PAUSE`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputAction {
+    Tap {
+        x: u32,
+        y: u32,
+    },
+
+    LongPress {
+        x: u32,
+        y: u32,
+
+        duration_ms: Option<u64>,
+    },
+
+    Swipe {
+        from_x: u32,
+        from_y: u32,
+        to_x:   u32,
+        to_y:   u32,
+
+        duration_ms: Option<u64>,
+    },
+
+    Drag {
+        from_x: u32,
+        from_y: u32,
+        to_x:   u32,
+        to_y:   u32,
+
+        duration_ms: Option<u64>,
+    },
+
+    Text {
+        text: String,
+    },
+
+    Key {
+        key: String,
+
+        #[serde(default)] long_press: bool,
+    },
+
+    KeyCombination {
+        keys: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct InputEvent {
+    #[serde(flatten)] pub action: InputAction,
+
+    pub source:     Option<InputSource>,
+    pub display_id: Option<u32>,
+}
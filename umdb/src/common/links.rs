@@ -1,15 +1,25 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
 
-#[derive(Serialize)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum OpenDeepLinkResult {
     Started,
     LaunchedInExistingInstance,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum OpenDeepLinkError {
     CannotRunProcess(String),
     BadExitCode(Option<i32>),
     DebugBridgePathMissing,
     CommandFailed(String),
+    DeviceUnresponsive,
+}
+
+// Restricts which app handles a link, and passes string extras along with the intent.
+#[derive(Default)]
+pub struct DeepLinkOptions<'a> {
+    pub package: Option<&'a str>,
+    pub extras:  Option<&'a BTreeMap<String, String>>,
 }
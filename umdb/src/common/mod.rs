@@ -1,3 +1,9 @@
+pub mod qr;
+pub mod ui;
+pub mod flow;
 pub mod links;
+pub mod input;
 pub mod device;
+pub mod fan_out;
+pub mod baseline;
 pub mod executable;
@@ -0,0 +1,120 @@
+use qrcode::{render::svg, Color, QrCode};
+use serde::{Deserialize, Serialize};
+
+const QUIET_ZONE_MODULES: usize = 4;
+
+// Codes scan at much smaller sizes, larger ones only take longer to render.
+const MAXIMUM_SCALE: u32 = 32;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default] Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub enum QrCodeError {
+    DataTooLong,
+    InvalidScale(u32),
+    CannotEncodeImage(String),
+}
+
+// Renders the data as a QR code image, where each module is `scale` pixels wide.
+pub fn render_qr_code(data: &str, format: QrFormat, scale: u32) -> Result<Vec<u8>, QrCodeError> {
+    if !(1..=MAXIMUM_SCALE).contains(&scale) {
+        return Err(QrCodeError::InvalidScale(scale));
+    }
+
+    let code = QrCode::new(data).map_err(|_| QrCodeError::DataTooLong)?;
+
+    match format {
+        QrFormat::Svg => Ok(
+            code
+            .render::<svg::Color>()
+            .module_dimensions(scale, scale)
+            .build()
+            .into_bytes()
+        ),
+
+        QrFormat::Png => render_png(&code, scale as usize),
+    }
+}
+
+fn render_png(code: &QrCode, scale: usize) -> Result<Vec<u8>, QrCodeError> {
+    let modules    = code.to_colors();
+    let code_width = code.width();
+    let width      = (code_width + 2 * QUIET_ZONE_MODULES) * scale;
+
+    let pixels = (0..width * width)
+    .map(|index| {
+        let (x, y) = ((index % width) / scale, (index / width) / scale);
+
+        let is_dark = x >= QUIET_ZONE_MODULES
+        && y >= QUIET_ZONE_MODULES
+        && x < code_width + QUIET_ZONE_MODULES
+        && y < code_width + QUIET_ZONE_MODULES
+        && modules[(y - QUIET_ZONE_MODULES) * code_width + x - QUIET_ZONE_MODULES] == Color::Dark;
+
+        if is_dark { 0 } else { 255 }
+    })
+    .collect::<Vec<u8>>();
+
+    let mut image = vec![];
+
+    let mut encoder = png::Encoder::new(&mut image, width as u32, width as u32);
+
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+    .write_header()
+    .and_then(|mut writer| writer.write_image_data(&pixels))
+    .map_err(|error| QrCodeError::CannotEncodeImage(error.to_string()))?;
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_qr_code, QrCodeError, QrFormat};
+
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn renders_png_and_svg_images() {
+        let payload = "WIFI:T:ADB;S:studio-abc;P:secret;;";
+
+        let image = render_qr_code(payload, QrFormat::Png, 1).unwrap();
+
+        assert!(image.starts_with(PNG_SIGNATURE));
+
+        // The IHDR chunk gives the width: 29 modules of a version 3 code, and a quiet zone of 4 modules on each side.
+        assert_eq!(image[16..20], 37u32.to_be_bytes());
+
+        let image = render_qr_code(payload, QrFormat::Png, 8).unwrap();
+
+        assert_eq!(image[16..20], (37u32 * 8).to_be_bytes());
+
+        let image = String::from_utf8(render_qr_code(payload, QrFormat::Svg, 8).unwrap()).unwrap();
+
+        assert!(image.starts_with("<?xml"));
+        assert!(image.contains("<svg"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_scales() {
+        assert!(matches!(render_qr_code("WIFI:T:ADB;;", QrFormat::Png, 0), Err(QrCodeError::InvalidScale(0))));
+        assert!(matches!(render_qr_code("WIFI:T:ADB;;", QrFormat::Svg, 33), Err(QrCodeError::InvalidScale(33))));
+        assert!(render_qr_code("WIFI:T:ADB;;", QrFormat::Png, 32).is_ok());
+    }
+}
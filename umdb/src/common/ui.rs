@@ -0,0 +1,228 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::input::InputError;
+
+#[derive(Serialize)]
+pub enum UiDumpError {
+    CannotRunProcess(String),
+    CommandFailed(String),
+    DebugBridgePathMissing,
+    DeviceUnresponsive,
+    MalformedDump(String),
+}
+
+#[derive(Serialize)]
+pub enum UiActionError {
+    Dump(UiDumpError),
+    Input(InputError),
+    ElementNotFound,
+    ElementWithoutBounds,
+    InvalidTextRegex(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+pub struct Bounds {
+    pub left:   i32,
+    pub top:    i32,
+    pub right:  i32,
+    pub bottom: i32,
+}
+
+impl Bounds {
+    pub fn center(&self) -> (i32, i32) {
+        ((self.left + self.right) / 2, (self.top + self.bottom) / 2)
+    }
+}
+
+// A view of the screen, as described by uiautomator.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct UiNode {
+    pub index: usize,
+    pub class: String,
+    pub package: Option<String>,
+    pub resource_id: Option<String>,
+    pub text: Option<String>,
+    pub content_desc: Option<String>,
+    pub bounds: Option<Bounds>,
+    pub clickable: bool,
+    pub long_clickable: bool,
+    pub scrollable: bool,
+    pub checkable: bool,
+    pub checked: bool,
+    pub enabled: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub selected: bool,
+    pub password: bool,
+    pub children: Vec<UiNode>,
+}
+
+impl UiNode {
+    pub fn is_interactive(&self) -> bool {
+        self.clickable || self.long_clickable || self.scrollable || self.checkable || self.class.ends_with("EditText")
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct UiHierarchy {
+    pub rotation: u32,
+    pub nodes: Vec<UiNode>,
+}
+
+impl UiHierarchy {
+    // Only keeps interactive nodes, which then take the place of their closest interactive ancestor.
+    pub fn compact(self) -> UiHierarchy {
+        UiHierarchy {
+            rotation: self.rotation,
+            nodes:    self.nodes.into_iter().flat_map(compact_node).collect(),
+        }
+    }
+
+    pub fn find(&self, selector: &UiSelector) -> Result<Option<&UiNode>, UiActionError> {
+        let text_regexp = selector
+        .text_regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|error| UiActionError::InvalidTextRegex(error.to_string()))?;
+
+        Ok(
+            self
+            .all_nodes()
+            .into_iter()
+            .filter(|node| selector.matches(node, text_regexp.as_ref()))
+            .nth(selector.instance.unwrap_or(0))
+        )
+    }
+
+    pub fn first_scrollable(&self) -> Option<&UiNode> {
+        self
+        .all_nodes()
+        .into_iter()
+        .find(|node| node.scrollable)
+    }
+
+    // Depth first, in the order uiautomator lists nodes.
+    fn all_nodes(&self) -> Vec<&UiNode> {
+        let mut nodes   = vec![];
+        let mut pending = self.nodes.iter().rev().collect::<Vec<_>>();
+
+        while let Some(node) = pending.pop() {
+            nodes.push(node);
+            pending.extend(node.children.iter().rev());
+        }
+
+        nodes
+    }
+}
+
+// All given criteria have to match. Resource ids can omit their package (`skip` for `com.example:id/skip`), and
+// classes their namespace (`Button` for `android.widget.Button`).
+#[derive(Clone, Default, Deserialize)]
+pub struct UiSelector {
+    pub text: Option<String>,
+    pub text_regex: Option<String>,
+    pub resource_id: Option<String>,
+    pub content_desc: Option<String>,
+    pub class: Option<String>,
+
+    // Position of the node among its siblings, as reported by uiautomator.
+    pub index: Option<usize>,
+
+    // Which of the matching nodes to pick, the first one by default.
+    pub instance: Option<usize>,
+}
+
+impl UiSelector {
+    pub fn describe(&self) -> String {
+        let criteria = [
+            ("text", self.text.clone()),
+            ("text_regex", self.text_regex.clone()),
+            ("resource_id", self.resource_id.clone()),
+            ("content_desc", self.content_desc.clone()),
+            ("class", self.class.clone()),
+            ("index", self.index.map(|index| index.to_string())),
+            ("instance", self.instance.map(|instance| instance.to_string())),
+        ];
+
+        criteria
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{name}={:?}", value?)))
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    fn matches(&self, node: &UiNode, text_regexp: Option<&Regex>) -> bool {
+        let text = node.text.as_deref().unwrap_or_default();
+
+        let resource_id_matches = |resource_id: &String| node.resource_id.as_ref().is_some_and(|node_resource_id| {
+            node_resource_id == resource_id || node_resource_id.ends_with(&format!(":id/{resource_id}"))
+        });
+
+        self.text.as_ref().is_none_or(|expected| expected == text)
+        && text_regexp.is_none_or(|regexp| regexp.is_match(text))
+        && self.resource_id.as_ref().is_none_or(resource_id_matches)
+        && self.content_desc.as_ref().is_none_or(|content_desc| node.content_desc.as_ref() == Some(content_desc))
+        && self.class.as_ref().is_none_or(|class| node.class == *class || node.class.ends_with(&format!(".{class}")))
+        && self.index.is_none_or(|index| node.index == index)
+    }
+}
+
+// Content comes into view from the given direction: scrolling down swipes up.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollDirection {
+    #[default] Down,
+    Up,
+    Left,
+    Right,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UiAction {
+    Tap,
+
+    LongPress {
+        duration_ms: Option<u64>,
+    },
+
+    // Replaces the text of a field.
+    SetText {
+        text: String,
+    },
+
+    // Gives up at the end of the list, after the timeout of the request, or after the given number of swipes.
+    ScrollUntilVisible {
+        #[serde(default)] direction: ScrollDirection,
+
+        max_scrolls: Option<u32>,
+    },
+}
+
+// Without an action, waits for the element to appear. Without a timeout, looks for the element once, or scrolls for
+// 30 seconds at most.
+#[derive(Deserialize)]
+pub struct UiActionRequest {
+    pub selector: UiSelector,
+    pub action: Option<UiAction>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct UiDumpOptions {
+    #[serde(default)] pub compact: bool,
+}
+
+fn compact_node(mut node: UiNode) -> Vec<UiNode> {
+    let children = std::mem::take(&mut node.children)
+    .into_iter()
+    .flat_map(compact_node)
+    .collect();
+
+    match node.is_interactive() {
+        true  => vec![UiNode { children, ..node }],
+        false => children,
+    }
+}
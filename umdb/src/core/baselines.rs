@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::baseline::Baseline;
+
+use super::{storage::{read_data_file, write_data_file, StorageError}, Configuration};
+
+const BASELINES_FILE_NAME: &str = "baselines.json";
+
+#[derive(Debug, Serialize)]
+pub enum BaselinesError {
+    Storage(StorageError),
+    BaselineNotFound(String),
+}
+
+// Known good device states, keyed by name, that devices are reset to between test runs.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Baselines {
+    pub baselines: BTreeMap<String, Baseline>,
+}
+
+impl Baselines {
+    pub fn load(configuration: &Configuration) -> Result<Baselines, BaselinesError> {
+        read_data_file(configuration, BASELINES_FILE_NAME).map_err(BaselinesError::Storage)
+    }
+
+    pub fn save(&self, configuration: &Configuration) -> Result<(), BaselinesError> {
+        write_data_file(configuration, BASELINES_FILE_NAME, self).map_err(BaselinesError::Storage)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Baseline, BaselinesError> {
+        self
+        .baselines
+        .get(name)
+        .ok_or_else(|| BaselinesError::BaselineNotFound(name.to_string()))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Baseline, BaselinesError> {
+        self
+        .baselines
+        .remove(name)
+        .ok_or_else(|| BaselinesError::BaselineNotFound(name.to_string()))
+    }
+}
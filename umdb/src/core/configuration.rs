@@ -1,12 +1,198 @@
-use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OperationPolicy {
+    pub timeout_ms: u64,
+
+    // Number of additional attempts made after a timeout.
+    pub retries: u32,
+}
+
+impl OperationPolicy {
+    pub const fn new(timeout_ms: u64, retries: u32) -> OperationPolicy {
+        OperationPolicy { timeout_ms, retries }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+// Operations missing from deserialized policies get their default policy.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationPolicies {
+    pub device_listing:    OperationPolicy,
+    pub device_properties: OperationPolicy,
+    pub network_discovery: OperationPolicy,
+    pub mdns_discovery:    OperationPolicy,
+    pub deep_link:         OperationPolicy,
+    pub connection:        OperationPolicy,
+    pub pairing:           OperationPolicy,
+
+    // How long a freshly connected device has to reach the `device` state.
+    pub device_ready: OperationPolicy,
+
+    // Any other command run on a device.
+    pub shell: OperationPolicy,
+}
+
+impl OperationPolicies {
+    pub fn new() -> OperationPolicies {
+        OperationPolicies {
+            device_listing:    OperationPolicy::new(5_000, 1),
+            device_properties: OperationPolicy::new(1_000, 0),
+            network_discovery: OperationPolicy::new(2_000, 0),
+            mdns_discovery:    OperationPolicy::new(5_000, 0),
+            deep_link:         OperationPolicy::new(10_000, 0),
+            connection:        OperationPolicy::new(5_000, 1),
+            pairing:           OperationPolicy::new(10_000, 0),
+            device_ready:      OperationPolicy::new(10_000, 0),
+            shell:             OperationPolicy::new(10_000, 0),
+        }
+    }
+}
+
+impl Default for OperationPolicies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// What unlocks the screen of a device. Written `{ "pin": "1234" }` or `{ "pattern": [1, 2, 3, 6, 9] }` in credential
+// files.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockCredential {
+    Pin(String),
+
+    // Dots of the 3 by 3 grid in the order they are joined, numbered from 1 to 9 row by row.
+    Pattern(Vec<u8>),
+}
+
+#[derive(Serialize)]
+pub enum UnlockCredentialsError {
+    CannotRead(String),
+    Malformed(String),
+}
 
 #[derive(Clone, Serialize)]
 pub struct Configuration {
     pub adb_command: Option<String>,
+
+    // Directory holding the files a team shares, such as the link catalog. Usually the directory of the configuration file.
+    pub data_directory: Option<PathBuf>,
+
+    // CSV file in the format of the Google Play supported devices list, such as the full list exported from the Play
+    // Console, or one naming in-house or unreleased hardware. Only a stub catalog is bundled.
+    pub device_catalog_path: Option<PathBuf>,
+
+    // Where flow runs save their screenshots. A directory of the system temporary directory by default.
+    pub artifacts_directory: Option<PathBuf>,
+
+    // Maximum number of devices a single multi-device request works on at the same time.
+    pub fan_out_concurrency: usize,
+
+    // Number of launches kept in memory by the launch history.
+    pub launch_history_limit: usize,
+
+    // Keyed by device id. Never sent back through the REST API.
+    #[serde(skip_serializing)] pub unlock_credentials: BTreeMap<String, UnlockCredential>,
+
+    // JSON file mapping device ids to credentials, read on each unlock. Kept apart from the data directory, which is
+    // shared through git.
+    pub unlock_credentials_path: Option<PathBuf>,
+
+    pub timeouts: OperationPolicies,
 }
 
 impl Configuration {
     pub fn new() -> Configuration {
-        Configuration { adb_command: None }
+        Configuration {
+            adb_command:             None,
+            data_directory:          None,
+            device_catalog_path:     None,
+            artifacts_directory:     None,
+            fan_out_concurrency:     4,
+            launch_history_limit:    1000,
+            unlock_credentials:      BTreeMap::new(),
+            unlock_credentials_path: None,
+            timeouts:                OperationPolicies::new(),
+        }
+    }
+
+    // Credentials set from Rust take precedence over those of the credentials file.
+    pub fn find_unlock_credential(&self, device_id: &str) -> Result<Option<UnlockCredential>, UnlockCredentialsError> {
+        if let Some(credential) = self.unlock_credentials.get(device_id) {
+            return Ok(Some(credential.clone()));
+        }
+
+        let Some(path) = &self.unlock_credentials_path else {
+            return Ok(None);
+        };
+
+        let contents = fs::read_to_string(path).map_err(|error| UnlockCredentialsError::CannotRead(error.to_string()))?;
+
+        let mut credentials = serde_json
+        ::from_str::<BTreeMap<String, UnlockCredential>>(&contents)
+        .map_err(|error| UnlockCredentialsError::Malformed(error.to_string()))?;
+
+        Ok(credentials.remove(device_id))
+    }
+
+    pub fn artifacts_directory(&self) -> PathBuf {
+        self
+        .artifacts_directory
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("umdb"))
+    }
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Configuration, OperationPolicies, UnlockCredential, UnlockCredentialsError};
+
+    #[test]
+    fn fills_missing_operations_with_default_policies() {
+        let policies = serde_json::from_str::<OperationPolicies>(r#"{ "shell": { "timeout_ms": 2500, "retries": 2 } }"#).unwrap();
+
+        assert_eq!((policies.shell.timeout_ms, policies.shell.retries), (2500, 2));
+        assert_eq!((policies.device_listing.timeout_ms, policies.device_listing.retries), (5000, 1));
+    }
+
+    #[test]
+    fn finds_unlock_credentials() {
+        let path = std::env::temp_dir().join(format!("umdb-unlock-credentials-{}.json", std::process::id()));
+
+        fs::write(&path, r#"{ "R58M123": { "pin": "0000" }, "emulator-5554": { "pattern": [1, 2, 3, 6, 9] } }"#).unwrap();
+
+        let mut configuration = Configuration::new();
+
+        assert!(matches!(configuration.find_unlock_credential("R58M123"), Ok(None)));
+
+        configuration.unlock_credentials_path = Some(path.clone());
+        configuration.unlock_credentials.insert("R58M123".to_string(), UnlockCredential::Pin("1234".to_string()));
+
+        assert!(matches!(configuration.find_unlock_credential("R58M123"), Ok(Some(UnlockCredential::Pin(pin))) if pin == "1234"));
+        assert!(matches!(configuration.find_unlock_credential("emulator-5554"), Ok(Some(UnlockCredential::Pattern(dots))) if dots == [1, 2, 3, 6, 9]));
+        assert!(matches!(configuration.find_unlock_credential("ZZ999"), Ok(None)));
+
+        fs::write(&path, r#"{ "emulator-5554": { "password": "hunter2" } }"#).unwrap();
+
+        assert!(matches!(configuration.find_unlock_credential("emulator-5554"), Err(UnlockCredentialsError::Malformed(_))));
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(configuration.find_unlock_credential("emulator-5554"), Err(UnlockCredentialsError::CannotRead(_))));
     }
 }
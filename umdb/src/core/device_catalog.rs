@@ -0,0 +1,266 @@
+use std::{collections::HashMap, fs, io::Read, path::{Path, PathBuf}, sync::{Arc, OnceLock}};
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+
+use super::Configuration;
+
+// A stub, not the Google Play supported devices list: a few dozen hand-picked Pixel and Galaxy phones, so most devices
+// are listed as not in catalog. Set `device_catalog_path` to a list exported from the Play Console for actual lookups,
+// or replace this file with it, compressed with `gzip -9`.
+const STUB_CATALOG: &[u8] = include_bytes!("../../data/supported-devices-stub.csv.gz");
+
+#[derive(Debug, Serialize)]
+pub enum DeviceCatalogError {
+    CannotReadCatalog(String),
+}
+
+struct CatalogEntry {
+    brand: String,
+    device: String,
+    marketing_name: String,
+}
+
+// Maps models to the names devices are sold under, in the format of the Google Play supported devices list:
+// `Retail Branding,Marketing Name,Device,Model`.
+#[derive(Default)]
+pub struct DeviceCatalog {
+    entries: HashMap<String, Vec<CatalogEntry>>,
+}
+
+impl DeviceCatalog {
+    // Only holds the stub catalog, see `STUB_CATALOG`.
+    pub fn bundled() -> &'static DeviceCatalog {
+        static CATALOG: OnceLock<DeviceCatalog> = OnceLock::new();
+
+        CATALOG.get_or_init(|| {
+            let mut contents = vec![];
+
+            GzDecoder::new(STUB_CATALOG)
+            .read_to_end(&mut contents)
+            .expect("The bundled device catalog is not a valid gzip file");
+
+            DeviceCatalog::parse(&decode_text(&contents))
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<DeviceCatalog, DeviceCatalogError> {
+        let contents = fs::read(path).map_err(|error| DeviceCatalogError::CannotReadCatalog(error.to_string()))?;
+
+        Ok(DeviceCatalog::parse(&decode_text(&contents)))
+    }
+
+    pub fn parse(contents: &str) -> DeviceCatalog {
+        let mut catalog = DeviceCatalog::default();
+
+        for line in contents.lines().skip(1) {
+            let fields = split_csv_line(line);
+
+            let [brand, marketing_name, device, model] = fields.as_slice() else {
+                continue;
+            };
+
+            if marketing_name.is_empty() {
+                continue;
+            }
+
+            catalog
+            .entries
+            .entry(normalize(model))
+            .or_default()
+            .push(CatalogEntry {
+                brand:          normalize(brand),
+                device:         normalize(device),
+                marketing_name: marketing_name.to_string(),
+            });
+        }
+
+        catalog
+    }
+
+    // Several devices can share a model name, in which case the device name, then the manufacturer, tell them apart.
+    pub fn find_marketing_name(&self, manufacturer: Option<&str>, model: &str, device: Option<&str>) -> Option<&str> {
+        let candidates = self.entries.get(&normalize(model))?;
+
+        let device       = device.map(normalize);
+        let manufacturer = manufacturer.map(normalize);
+
+        candidates
+        .iter()
+        .max_by_key(|entry| (
+            device.as_ref() == Some(&entry.device),
+            manufacturer.as_ref() == Some(&entry.brand),
+        ))
+        .map(|entry| entry.marketing_name.as_str())
+    }
+}
+
+// The configured file, such as the full Play Console list or one describing in-house hardware, wins over the bundled stub.
+pub fn find_marketing_name(overrides: Option<&DeviceCatalog>, manufacturer: Option<&str>, model: &str, device: Option<&str>) -> Option<String> {
+    overrides
+    .and_then(|catalog| catalog.find_marketing_name(manufacturer, model, device))
+    .or_else(|| DeviceCatalog::bundled().find_marketing_name(manufacturer, model, device))
+    .map(str::to_string)
+}
+
+// The override file is read again only when the configured path changes.
+#[derive(Default)]
+pub struct CatalogOverrides {
+    path:    Option<PathBuf>,
+    catalog: Option<Arc<DeviceCatalog>>,
+}
+
+impl CatalogOverrides {
+    pub fn get(&mut self, configuration: &Configuration) -> Result<Option<Arc<DeviceCatalog>>, DeviceCatalogError> {
+        if self.path != configuration.device_catalog_path {
+            self.catalog = configuration
+            .device_catalog_path
+            .as_deref()
+            .map(DeviceCatalog::load)
+            .transpose()?
+            .map(Arc::new);
+
+            self.path = configuration.device_catalog_path.clone();
+        }
+
+        Ok(self.catalog.clone())
+    }
+}
+
+// `adb devices -l` replaces characters other than letters and digits with underscores, and brands differ in case.
+fn normalize(value: &str) -> String {
+    value
+    .chars()
+    .map(|character| match character.is_ascii_alphanumeric() {
+        true  => character.to_ascii_lowercase(),
+        false => '_',
+    })
+    .collect()
+}
+
+// The Play Console exports the list in UTF-16.
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => String::from_utf16_lossy(
+            &rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>()
+        ),
+
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// Fields are quoted when they contain commas, with quotes escaped by doubling them.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields     = vec![];
+    let mut field      = String::new();
+    let mut is_quoted  = false;
+    let mut characters = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match (character, is_quoted) {
+            ('"', true) if characters.peek() == Some(&'"') => {
+                characters.next();
+
+                field.push('"');
+            },
+
+            ('"', _)       => is_quoted = !is_quoted,
+            (',', false)   => fields.push(std::mem::take(&mut field)),
+            (character, _) => field.push(character),
+        }
+    }
+
+    fields.push(field);
+
+    fields
+    .into_iter()
+    .map(|field| field.trim().to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use crate::core::Configuration;
+
+    use super::{find_marketing_name, CatalogOverrides, DeviceCatalog};
+
+    // Rows of the Play Console export, where a model name can be shared by devices of different brands.
+    const CATALOG: &str = "\
+Retail Branding,Marketing Name,Device,Model
+Samsung,Galaxy S10,beyond1,SM-G973F
+Samsung,\"Galaxy Tab S6, Wi-Fi\",gts6lwifi,SM-T860
+Alcatel,1S,5024D_EEA,5024D
+Alcatel,1S (2020),Seoul,5024D
+Acme,,acme1,AC-1
+";
+
+    #[test]
+    fn finds_marketing_names_of_models_as_adb_reports_them() {
+        let catalog = DeviceCatalog::parse(CATALOG);
+
+        assert_eq!(catalog.find_marketing_name(Some("samsung"), "SM_G973F", Some("beyond1")), Some("Galaxy S10"));
+        assert_eq!(catalog.find_marketing_name(None, "SM-T860", None), Some("Galaxy Tab S6, Wi-Fi"));
+        assert_eq!(catalog.find_marketing_name(Some("TCL"), "5024D", Some("Seoul")), Some("1S (2020)"));
+        assert_eq!(catalog.find_marketing_name(None, "AC-1", None), None);
+        assert_eq!(catalog.find_marketing_name(None, "Pixel 7", None), None);
+    }
+
+    #[test]
+    fn reads_utf16_exports() {
+        let export = [0xFF, 0xFE]
+        .into_iter()
+        .chain(CATALOG.encode_utf16().flat_map(u16::to_le_bytes))
+        .collect::<Vec<_>>();
+
+        let path = std::env::temp_dir().join(format!("umdb-catalog-utf16-{}.csv", std::process::id()));
+
+        fs::write(&path, export).unwrap();
+
+        let catalog = DeviceCatalog::load(&path).ok().unwrap();
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(catalog.find_marketing_name(None, "SM-G973F", None), Some("Galaxy S10"));
+    }
+
+    #[test]
+    fn prefers_overrides_to_the_bundled_catalog() {
+        let overrides = DeviceCatalog::parse("Retail Branding,Marketing Name,Device,Model\nGoogle,Pixel 3 (lab),blueline,Pixel 3\n");
+
+        assert_eq!(find_marketing_name(None, None, "Pixel 3", Some("blueline")).as_deref(), Some("Pixel 3"));
+        assert_eq!(find_marketing_name(Some(&overrides), None, "Pixel 3", Some("blueline")).as_deref(), Some("Pixel 3 (lab)"));
+        assert_eq!(find_marketing_name(Some(&overrides), None, "Pixel 3a", None).as_deref(), Some("Pixel 3a"));
+    }
+
+    #[test]
+    fn reads_the_override_file_again_only_when_its_path_changes() {
+        let path = std::env::temp_dir().join(format!("umdb-catalog-overrides-{}.csv", std::process::id()));
+
+        fs::write(&path, CATALOG).unwrap();
+
+        let mut configuration = Configuration::new();
+        let mut overrides     = CatalogOverrides::default();
+
+        assert!(overrides.get(&configuration).ok().unwrap().is_none());
+
+        configuration.device_catalog_path = Some(path.clone());
+
+        let first  = overrides.get(&configuration).ok().unwrap().unwrap();
+        let second = overrides.get(&configuration).ok().unwrap().unwrap();
+
+        fs::remove_file(path).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+
+        configuration.device_catalog_path = None;
+
+        assert!(overrides.get(&configuration).ok().unwrap().is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::device::DeviceProfile;
+
+use super::{storage::{read_data_file, write_data_file, StorageError}, Configuration};
+
+const PROFILES_FILE_NAME: &str = "devices.json";
+
+#[derive(Debug, Serialize)]
+pub enum DeviceProfilesError {
+    Storage(StorageError),
+    ProfileNotFound(String),
+}
+
+// What the team knows about its devices, keyed by hardware serial so that it follows a device across transports.
+#[derive(Default, Serialize, Deserialize)]
+pub struct DeviceProfiles {
+    pub profiles: BTreeMap<String, DeviceProfile>,
+}
+
+impl DeviceProfiles {
+    pub fn load(configuration: &Configuration) -> Result<DeviceProfiles, DeviceProfilesError> {
+        read_data_file(configuration, PROFILES_FILE_NAME).map_err(DeviceProfilesError::Storage)
+    }
+
+    // Listing devices works without a data directory: devices then simply have no profile.
+    pub fn load_or_default(configuration: &Configuration) -> Result<DeviceProfiles, DeviceProfilesError> {
+        match DeviceProfiles::load(configuration) {
+            Err(DeviceProfilesError::Storage(StorageError::DataDirectoryMissing)) => Ok(DeviceProfiles::default()),
+
+            result => result,
+        }
+    }
+
+    pub fn save(&self, configuration: &Configuration) -> Result<(), DeviceProfilesError> {
+        write_data_file(configuration, PROFILES_FILE_NAME, self).map_err(DeviceProfilesError::Storage)
+    }
+
+    pub fn get(&self, serial: &str) -> Result<&DeviceProfile, DeviceProfilesError> {
+        self
+        .profiles
+        .get(serial)
+        .ok_or_else(|| DeviceProfilesError::ProfileNotFound(serial.to_string()))
+    }
+
+    pub fn remove(&mut self, serial: &str) -> Result<DeviceProfile, DeviceProfilesError> {
+        self
+        .profiles
+        .remove(serial)
+        .ok_or_else(|| DeviceProfilesError::ProfileNotFound(serial.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{common::device::DeviceProfile, core::{storage::StorageError, Configuration}};
+
+    use super::{DeviceProfiles, DeviceProfilesError, PROFILES_FILE_NAME};
+
+    fn data_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("umdb-{name}-{}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&directory);
+
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn defaults_to_no_profiles() {
+        let mut configuration = Configuration::new();
+
+        assert!(matches!(DeviceProfiles::load(&configuration), Err(DeviceProfilesError::Storage(StorageError::DataDirectoryMissing))));
+        assert!(DeviceProfiles::load_or_default(&configuration).unwrap().profiles.is_empty());
+
+        // A data directory without the file is a team that has not described its devices yet.
+        configuration.data_directory = Some(data_directory("device-profiles-default"));
+
+        assert!(DeviceProfiles::load(&configuration).unwrap().profiles.is_empty());
+    }
+
+    #[test]
+    fn round_trips_profiles() {
+        let mut configuration = Configuration::new();
+
+        configuration.data_directory = Some(data_directory("device-profiles-round-trip"));
+
+        let mut profiles = DeviceProfiles::default();
+
+        profiles.profiles.insert("R58M12ABCDE".to_string(), DeviceProfile {
+            nickname: Some("Blue S23".to_string()),
+            labels:   vec!["smoke".to_string()],
+            owner:    Some("qa".to_string()),
+            notes:    None,
+        });
+
+        profiles.save(&configuration).unwrap();
+
+        let mut profiles = DeviceProfiles::load(&configuration).unwrap();
+
+        assert_eq!(profiles.get("R58M12ABCDE").unwrap().nickname.as_deref(), Some("Blue S23"));
+        assert_eq!(profiles.get("R58M12ABCDE").unwrap().labels, ["smoke"]);
+        assert!(matches!(profiles.get("emulator-5554"), Err(DeviceProfilesError::ProfileNotFound(_))));
+
+        assert_eq!(profiles.remove("R58M12ABCDE").unwrap().owner.as_deref(), Some("qa"));
+        assert!(matches!(profiles.remove("R58M12ABCDE"), Err(DeviceProfilesError::ProfileNotFound(_))));
+    }
+
+    #[test]
+    fn loads_profiles_with_missing_fields() {
+        let directory = data_directory("device-profiles-load");
+
+        fs::write(directory.join(PROFILES_FILE_NAME), r#"{ "profiles": { "R58M12ABCDE": { "labels": ["nightly"] }, "emulator-5554": {} } }"#).unwrap();
+
+        let mut configuration = Configuration::new();
+
+        configuration.data_directory = Some(directory.clone());
+
+        let profiles = DeviceProfiles::load(&configuration).unwrap();
+
+        assert_eq!(profiles.get("R58M12ABCDE").unwrap().labels, ["nightly"]);
+        assert!(profiles.get("emulator-5554").unwrap().nickname.is_none());
+
+        fs::write(directory.join(PROFILES_FILE_NAME), r#"{ "profiles": ["R58M12ABCDE"] }"#).unwrap();
+
+        assert!(matches!(DeviceProfiles::load(&configuration), Err(DeviceProfilesError::Storage(StorageError::Malformed(_)))));
+    }
+}
@@ -0,0 +1,90 @@
+use std::{collections::BTreeMap, time::{Duration, Instant}};
+
+// Sessions whose client went away without ending them end on their own once their lease expires.
+pub const DEFAULT_KEEP_AWAKE_LEASE: Duration = Duration::from_secs(30 * 60);
+
+struct KeepAwakeSession {
+    // The `stay_on_while_plugged_in` setting the device had before.
+    previous_value: u32,
+
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+pub struct KeepAwakeSessions {
+    sessions: BTreeMap<String, KeepAwakeSession>,
+}
+
+impl KeepAwakeSessions {
+    // Starting a session again renews its lease, and keeps the value the setting had when the session first started.
+    pub fn start(&mut self, device_id: &str, previous_value: u32, lease: Duration) {
+        let expires_at = Instant::now() + lease;
+
+        self
+        .sessions
+        .entry(device_id.to_string())
+        .and_modify(|session| session.expires_at = expires_at)
+        .or_insert(KeepAwakeSession { previous_value, expires_at });
+    }
+
+    // Returns the value to restore.
+    pub fn end(&mut self, device_id: &str) -> Option<u32> {
+        self
+        .sessions
+        .remove(device_id)
+        .map(|session| session.previous_value)
+    }
+
+    // Sessions renewed since their expiry was planned are kept.
+    pub fn end_if_expired(&mut self, device_id: &str) -> Option<u32> {
+        match self.sessions.get(device_id)?.expires_at <= Instant::now() {
+            true  => self.end(device_id),
+            false => None,
+        }
+    }
+
+    pub fn end_all(&mut self) -> Vec<(String, u32)> {
+        std::mem::take(&mut self.sessions)
+        .into_iter()
+        .map(|(device_id, session)| (device_id, session.previous_value))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::KeepAwakeSessions;
+
+    #[test]
+    fn keeps_the_first_previous_value() {
+        let mut sessions = KeepAwakeSessions::default();
+
+        sessions.start("emulator-5554", 0, Duration::from_secs(60));
+        sessions.start("emulator-5554", 7, Duration::from_secs(60));
+
+        assert_eq!(sessions.end("emulator-5554"), Some(0));
+        assert_eq!(sessions.end("emulator-5554"), None);
+    }
+
+    #[test]
+    fn ends_expired_sessions_only() {
+        let mut sessions = KeepAwakeSessions::default();
+
+        sessions.start("emulator-5554", 2, Duration::ZERO);
+        sessions.start("R58M123", 0, Duration::from_secs(60));
+
+        assert_eq!(sessions.end_if_expired("emulator-5554"), Some(2));
+        assert_eq!(sessions.end_if_expired("R58M123"), None);
+        assert_eq!(sessions.end_if_expired("ZZ999"), None);
+
+        // Renewing an expired session pushes its expiry back.
+        sessions.start("R58M123", 7, Duration::ZERO);
+        sessions.start("R58M123", 7, Duration::from_secs(60));
+
+        assert_eq!(sessions.end_if_expired("R58M123"), None);
+        assert_eq!(sessions.end_all(), [("R58M123".to_string(), 0)]);
+        assert!(sessions.end_all().is_empty());
+    }
+}
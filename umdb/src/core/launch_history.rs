@@ -0,0 +1,184 @@
+use std::{collections::VecDeque, fs::{self, OpenOptions}, io::Write, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::links::{OpenDeepLinkError, OpenDeepLinkResult};
+
+use super::{storage::data_file_path, Configuration};
+
+const HISTORY_FILE_NAME: &str = "launch-history.jsonl";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub link:        String,
+    pub link_id:     Option<String>,
+    pub device_id:   String,
+    pub timestamp:   u64,
+    pub duration_ms: u64,
+    pub result:      Result<OpenDeepLinkResult, OpenDeepLinkError>,
+}
+
+impl LaunchRecord {
+    pub fn new(device_id: &str, link: &str, link_id: Option<&str>, started_at: SystemTime, duration: Duration, result: &Result<OpenDeepLinkResult, OpenDeepLinkError>) -> LaunchRecord {
+        LaunchRecord {
+            link:        link.to_string(),
+            link_id:     link_id.map(str::to_string),
+            device_id:   device_id.to_string(),
+            timestamp:   started_at.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_millis() as u64,
+            duration_ms: duration.as_millis() as u64,
+            result:      result.clone(),
+        }
+    }
+}
+
+// Launches are appended to a file in the data directory when there is one, and the most recent ones are kept in memory.
+// Once the file holds twice as many launches as the limit, it is rewritten with the most recent ones only.
+#[derive(Default)]
+pub struct LaunchHistory {
+    records:         VecDeque<LaunchRecord>,
+    persisted_count: usize,
+    loaded:          bool,
+}
+
+impl LaunchHistory {
+    pub fn record(&mut self, configuration: &Configuration, record: LaunchRecord) {
+        self.load_once(configuration);
+
+        if let Ok(path) = data_file_path(configuration, HISTORY_FILE_NAME) {
+            let appended = OpenOptions
+            ::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", serde_json::to_string(&record).unwrap()));
+
+            match appended {
+                Ok(())     => self.persisted_count += 1,
+                Err(error) => log::warn!("Could not persist a launch to the launch history: {error}"),
+            }
+        }
+
+        self.records.push_front(record);
+        self.records.truncate(configuration.launch_history_limit);
+
+        if self.persisted_count > configuration.launch_history_limit.saturating_mul(2) {
+            self.compact(configuration);
+        }
+    }
+
+    // Most recent launches first.
+    pub fn list(&mut self, configuration: &Configuration, device_id: Option<&str>, limit: Option<usize>) -> Vec<LaunchRecord> {
+        self.load_once(configuration);
+
+        self
+        .records
+        .iter()
+        .filter(|record| device_id.is_none_or(|device_id| record.device_id == device_id))
+        .take(limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect()
+    }
+
+    fn load_once(&mut self, configuration: &Configuration) {
+        if self.loaded {
+            return;
+        }
+
+        self.loaded = true;
+
+        let Some(contents) = data_file_path(configuration, HISTORY_FILE_NAME).ok().and_then(|path| fs::read_to_string(path).ok()) else {
+            return;
+        };
+
+        let persisted = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<LaunchRecord>(line).ok())
+        .collect::<Vec<_>>();
+
+        self.persisted_count = persisted.len();
+
+        self.records.extend(persisted.into_iter().rev().take(configuration.launch_history_limit));
+    }
+
+    // Written to a temporary file first, so that a crash while compacting does not lose the history.
+    fn compact(&mut self, configuration: &Configuration) {
+        let Ok(path) = data_file_path(configuration, HISTORY_FILE_NAME) else {
+            return;
+        };
+
+        let contents = self
+        .records
+        .iter()
+        .rev()
+        .map(|record| serde_json::to_string(record).unwrap() + "\n")
+        .collect::<String>();
+
+        let temporary_path = path.with_extension("jsonl.tmp");
+
+        let compacted = fs::write(&temporary_path, contents).and_then(|_| fs::rename(&temporary_path, &path));
+
+        match compacted {
+            Ok(())     => self.persisted_count = self.records.len(),
+            Err(error) => log::warn!("Could not compact the launch history: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, time::{Duration, SystemTime}};
+
+    use crate::{common::links::OpenDeepLinkError, core::Configuration};
+
+    use super::{LaunchHistory, LaunchRecord, HISTORY_FILE_NAME};
+
+    fn data_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("umdb-{name}-{}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&directory);
+
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn compacts_the_file_once_it_holds_twice_the_limit() {
+        let directory = data_directory("launch-history");
+
+        let mut configuration = Configuration::new();
+
+        configuration.data_directory       = Some(directory.clone());
+        configuration.launch_history_limit = 3;
+
+        let mut history = LaunchHistory::default();
+
+        for index in 0..7 {
+            let result = Err(OpenDeepLinkError::DeviceUnresponsive);
+
+            history.record(&configuration, LaunchRecord::new("emulator-5554", &format!("app://{index}"), None, SystemTime::now(), Duration::ZERO, &result));
+        }
+
+        let contents = fs::read_to_string(directory.join(HISTORY_FILE_NAME)).unwrap();
+
+        let links = contents
+        .lines()
+        .map(|line| serde_json::from_str::<LaunchRecord>(line).unwrap().link)
+        .collect::<Vec<_>>();
+
+        assert_eq!(links, ["app://4", "app://5", "app://6"]);
+
+        // Reloading reads the compacted file back.
+        let mut reloaded = LaunchHistory::default();
+
+        let reloaded_links = reloaded
+        .list(&configuration, None, None)
+        .into_iter()
+        .map(|record| record.link)
+        .collect::<Vec<_>>();
+
+        assert_eq!(reloaded_links, ["app://6", "app://5", "app://4"]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
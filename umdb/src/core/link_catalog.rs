@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{storage::{read_data_file, write_data_file, StorageError}, Configuration, LinkParameter};
+
+const CATALOG_FILE_NAME: &str = "links.json";
+
+#[derive(Debug, Serialize)]
+pub enum LinkCatalogError {
+    CannotWriteCatalog(String),
+    CannotReadCatalog(String),
+    MalformedCatalog(String),
+    DataDirectoryMissing,
+    LinkNotFound(String),
+    InvalidLinkName,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CatalogLink {
+    pub name: String,
+    pub url:  String,
+
+    #[serde(default)] pub tags:    Vec<String>,
+    #[serde(default)] pub package: Option<String>,
+    #[serde(default)] pub extras:  BTreeMap<String, String>,
+
+    // Placeholders such as `{orderId}` in the URL are declared here and expanded when the link is launched.
+    #[serde(default)] pub parameters: BTreeMap<String, LinkParameter>,
+}
+
+#[derive(Serialize)]
+pub struct IdentifiedCatalogLink {
+    pub id: String,
+
+    #[serde(flatten)] pub link: CatalogLink,
+}
+
+// The catalog file is the source of truth: it is read on every access so that changes pulled from git show up immediately.
+#[derive(Default, Serialize, Deserialize)]
+pub struct LinkCatalog {
+    pub links: BTreeMap<String, CatalogLink>,
+}
+
+impl LinkCatalog {
+    pub fn load(configuration: &Configuration) -> Result<LinkCatalog, LinkCatalogError> {
+        Ok(read_data_file(configuration, CATALOG_FILE_NAME)?)
+    }
+
+    pub fn save(&self, configuration: &Configuration) -> Result<(), LinkCatalogError> {
+        Ok(write_data_file(configuration, CATALOG_FILE_NAME, self)?)
+    }
+
+    pub fn get(&self, id: &str) -> Result<&CatalogLink, LinkCatalogError> {
+        self
+        .links
+        .get(id)
+        .ok_or_else(|| LinkCatalogError::LinkNotFound(id.to_string()))
+    }
+
+    pub fn list(&self, tag: Option<&str>) -> Vec<IdentifiedCatalogLink> {
+        self
+        .links
+        .iter()
+        .filter(|(_, link)| tag.is_none_or(|tag| link.tags.iter().any(|link_tag| link_tag == tag)))
+        .map(|(id, link)| IdentifiedCatalogLink { id: id.clone(), link: link.clone() })
+        .collect()
+    }
+
+    // Inserts a new link under an identifier derived from its name, and returns that identifier.
+    pub fn insert(&mut self, link: CatalogLink) -> Result<String, LinkCatalogError> {
+        let base_id = slugify(&link.name);
+
+        if base_id.is_empty() {
+            return Err(LinkCatalogError::InvalidLinkName);
+        }
+
+        let id = (1..)
+        .map(|index| if index == 1 { base_id.clone() } else { format!("{base_id}-{index}") })
+        .find(|id| !self.links.contains_key(id))
+        .unwrap();
+
+        self.links.insert(id.clone(), link);
+
+        Ok(id)
+    }
+
+    pub fn replace(&mut self, id: &str, link: CatalogLink) {
+        self.links.insert(id.to_string(), link);
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<CatalogLink, LinkCatalogError> {
+        self
+        .links
+        .remove(id)
+        .ok_or_else(|| LinkCatalogError::LinkNotFound(id.to_string()))
+    }
+}
+
+impl From<StorageError> for LinkCatalogError {
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::DataDirectoryMissing => LinkCatalogError::DataDirectoryMissing,
+            StorageError::CannotRead(message)  => LinkCatalogError::CannotReadCatalog(message),
+            StorageError::CannotWrite(message) => LinkCatalogError::CannotWriteCatalog(message),
+            StorageError::Malformed(message)   => LinkCatalogError::MalformedCatalog(message),
+        }
+    }
+}
+
+fn slugify(name: &str) -> String {
+    name
+    .to_lowercase()
+    .split(|character: char| !character.is_alphanumeric())
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join("-")
+}
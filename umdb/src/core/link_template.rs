@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::CatalogLink;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterKind {
+    String,
+    Int,
+    Uuid,
+    Enum { values: Vec<String> },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LinkParameter {
+    #[serde(flatten)]  pub kind:    ParameterKind,
+    #[serde(default)] pub default: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub enum LinkTemplateError {
+    InvalidValue { parameter: String, value: String },
+    UndeclaredParameter(String),
+    UnknownVariable(String),
+    MissingVariable(String),
+    UnterminatedPlaceholder,
+}
+
+static UUID_REGEXP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}$").unwrap());
+
+enum TemplatePart<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+// Checks that every placeholder of the link is declared, and that declared defaults are valid.
+pub fn validate_link_template(link: &CatalogLink) -> Result<(), LinkTemplateError> {
+    for part in split_template(&link.url)? {
+        if let TemplatePart::Placeholder(name) = part {
+            if !link.parameters.contains_key(name) {
+                return Err(LinkTemplateError::UndeclaredParameter(name.to_string()));
+            }
+        }
+    }
+
+    for (name, parameter) in &link.parameters {
+        if let Some(default) = &parameter.default {
+            validate_value(name, &parameter.kind, default)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Replaces placeholders with their percent-encoded values, falling back to parameter defaults.
+pub fn expand_link_template(link: &CatalogLink, variables: &BTreeMap<String, Value>) -> Result<String, LinkTemplateError> {
+    if let Some(name) = variables.keys().find(|name| !link.parameters.contains_key(*name)) {
+        return Err(LinkTemplateError::UnknownVariable(name.clone()));
+    }
+
+    let mut url = String::with_capacity(link.url.len());
+
+    for part in split_template(&link.url)? {
+        let name = match part {
+            TemplatePart::Literal(literal)  => { url.push_str(literal); continue; },
+            TemplatePart::Placeholder(name) => name,
+        };
+
+        let parameter = link
+        .parameters
+        .get(name)
+        .ok_or_else(|| LinkTemplateError::UndeclaredParameter(name.to_string()))?;
+
+        let value = match variables.get(name) {
+            Some(Value::String(value)) => value.clone(),
+            Some(value)                => value.to_string(),
+
+            None => parameter
+            .default
+            .clone()
+            .ok_or_else(|| LinkTemplateError::MissingVariable(name.to_string()))?,
+        };
+
+        validate_value(name, &parameter.kind, &value)?;
+
+        url.push_str(&percent_encode(&value));
+    }
+
+    Ok(url)
+}
+
+fn split_template(template: &str) -> Result<Vec<TemplatePart<'_>>, LinkTemplateError> {
+    let mut parts     = vec![];
+    let mut remaining = template;
+
+    while let Some(start) = remaining.find('{') {
+        let end = remaining[start..].find('}').ok_or(LinkTemplateError::UnterminatedPlaceholder)? + start;
+
+        parts.push(TemplatePart::Literal(&remaining[..start]));
+        parts.push(TemplatePart::Placeholder(remaining[start + 1..end].trim()));
+
+        remaining = &remaining[end + 1..];
+    }
+
+    parts.push(TemplatePart::Literal(remaining));
+
+    Ok(parts)
+}
+
+fn validate_value(name: &str, kind: &ParameterKind, value: &str) -> Result<(), LinkTemplateError> {
+    let valid = match kind {
+        ParameterKind::String          => true,
+        ParameterKind::Int             => value.parse::<i64>().is_ok(),
+        ParameterKind::Uuid            => UUID_REGEXP.is_match(value),
+        ParameterKind::Enum { values } => values.iter().any(|allowed| allowed == value),
+    };
+
+    match valid {
+        true  => Ok(()),
+        false => Err(LinkTemplateError::InvalidValue { parameter: name.to_string(), value: value.to_string() }),
+    }
+}
+
+// Values are encoded as URI components wherever they appear, so that they cannot alter the structure of the link.
+fn percent_encode(value: &str) -> String {
+    value
+    .bytes()
+    .map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+
+        _ => format!("%{byte:02X}"),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::{json, Value};
+
+    use crate::core::CatalogLink;
+
+    use super::{expand_link_template, validate_link_template, LinkTemplateError};
+
+    const ORDER_LINK: &str = r#"{
+        "name": "Order details",
+        "url": "shop://orders/{orderId}?tab={ tab }&ref={ref}",
+        "parameters": {
+            "orderId": { "type": "uuid" },
+            "tab":     { "type": "enum", "values": ["summary", "tracking"], "default": "summary" },
+            "ref":     { "type": "string", "default": "umdb" }
+        }
+    }"#;
+
+    fn variables(value: Value) -> BTreeMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn expands_placeholders_with_values_and_defaults() {
+        let link = serde_json::from_str::<CatalogLink>(ORDER_LINK).unwrap();
+
+        let url = expand_link_template(&link, &variables(json!({ "orderId": "0f8fad5b-d9cb-469f-a165-70867728950e", "ref": "a b&c" }))).unwrap();
+
+        assert_eq!(url, "shop://orders/0f8fad5b-d9cb-469f-a165-70867728950e?tab=summary&ref=a%20b%26c");
+    }
+
+    #[test]
+    fn rejects_invalid_and_unknown_variables() {
+        let link = serde_json::from_str::<CatalogLink>(ORDER_LINK).unwrap();
+
+        let invalid_uuid = expand_link_template(&link, &variables(json!({ "orderId": "42" })));
+        let invalid_enum = expand_link_template(&link, &variables(json!({ "orderId": "0f8fad5b-d9cb-469f-a165-70867728950e", "tab": "returns" })));
+        let unknown      = expand_link_template(&link, &variables(json!({ "orderId": "0f8fad5b-d9cb-469f-a165-70867728950e", "user": "1" })));
+        let missing      = expand_link_template(&link, &BTreeMap::new());
+
+        assert!(matches!(invalid_uuid, Err(LinkTemplateError::InvalidValue { parameter, .. }) if parameter == "orderId"));
+        assert!(matches!(invalid_enum, Err(LinkTemplateError::InvalidValue { parameter, .. }) if parameter == "tab"));
+        assert!(matches!(unknown, Err(LinkTemplateError::UnknownVariable(name)) if name == "user"));
+        assert!(matches!(missing, Err(LinkTemplateError::MissingVariable(name)) if name == "orderId"));
+    }
+
+    #[test]
+    fn expands_integer_variables_given_as_json_numbers() {
+        let link = serde_json::from_str::<CatalogLink>(r#"{ "name": "Page", "url": "app://page/{page}", "parameters": { "page": { "type": "int" } } }"#).unwrap();
+
+        assert_eq!(expand_link_template(&link, &variables(json!({ "page": 3 }))).unwrap(), "app://page/3");
+    }
+
+    #[test]
+    fn validates_templates() {
+        let undeclared   = serde_json::from_str::<CatalogLink>(r#"{ "name": "Page", "url": "app://page/{page}" }"#).unwrap();
+        let unterminated = serde_json::from_str::<CatalogLink>(r#"{ "name": "Page", "url": "app://page/{page" }"#).unwrap();
+        let bad_default  = serde_json::from_str::<CatalogLink>(r#"{ "name": "Page", "url": "app://page/{page}", "parameters": { "page": { "type": "int", "default": "first" } } }"#).unwrap();
+
+        assert!(validate_link_template(&serde_json::from_str::<CatalogLink>(ORDER_LINK).unwrap()).is_ok());
+        assert!(matches!(validate_link_template(&undeclared), Err(LinkTemplateError::UndeclaredParameter(name)) if name == "page"));
+        assert!(matches!(validate_link_template(&unterminated), Err(LinkTemplateError::UnterminatedPlaceholder)));
+        assert!(matches!(validate_link_template(&bad_default), Err(LinkTemplateError::InvalidValue { .. })));
+    }
+}
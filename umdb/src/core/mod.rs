@@ -1,5 +1,20 @@
 mod umdb;
+mod storage;
+mod baselines;
+mod keep_awake;
+mod link_catalog;
+mod link_template;
 mod configuration;
+mod launch_history;
+mod device_catalog;
+mod device_profiles;
 
 pub use umdb::*;
-pub use configuration::Configuration;
+pub use baselines::*;
+pub use keep_awake::*;
+pub use link_catalog::*;
+pub use link_template::*;
+pub use launch_history::*;
+pub use device_catalog::*;
+pub use device_profiles::*;
+pub use configuration::{Configuration, OperationPolicies, OperationPolicy, UnlockCredential, UnlockCredentialsError};
@@ -0,0 +1,45 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::Configuration;
+
+#[derive(Debug, Serialize)]
+pub enum StorageError {
+    DataDirectoryMissing,
+    CannotRead(String),
+    CannotWrite(String),
+    Malformed(String),
+}
+
+// Files of the data directory are shared through git: a missing file is an empty one.
+pub fn read_data_file<T: Default + DeserializeOwned>(configuration: &Configuration, file_name: &str) -> Result<T, StorageError> {
+    let contents = match fs::read_to_string(data_file_path(configuration, file_name)?) {
+        Ok(contents)                                      => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(error)                                        => return Err(StorageError::CannotRead(error.to_string())),
+    };
+
+    serde_json::from_str(&contents).map_err(|error| StorageError::Malformed(error.to_string()))
+}
+
+pub fn write_data_file<T: Serialize>(configuration: &Configuration, file_name: &str, value: &T) -> Result<(), StorageError> {
+    let path = data_file_path(configuration, file_name)?;
+
+    // Pretty-printed with a trailing newline to keep diffs readable in git.
+    let contents = serde_json::to_string_pretty(value).unwrap() + "\n";
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| StorageError::CannotWrite(error.to_string()))?;
+    }
+
+    fs::write(path, contents).map_err(|error| StorageError::CannotWrite(error.to_string()))
+}
+
+pub fn data_file_path(configuration: &Configuration, file_name: &str) -> Result<PathBuf, StorageError> {
+    configuration
+    .data_directory
+    .as_ref()
+    .map(|directory| directory.join(file_name))
+    .ok_or(StorageError::DataDirectoryMissing)
+}
@@ -1,4 +1,6 @@
-use super::configuration::Configuration;
+use std::collections::BTreeMap;
+
+use super::{configuration::Configuration, device_catalog::CatalogOverrides, keep_awake::KeepAwakeSessions, launch_history::LaunchHistory};
 
 #[derive(PartialEq)]
 pub enum System {
@@ -7,12 +9,34 @@ pub enum System {
 }
 
 pub struct Umdb {
-    pub configuration: Configuration,
-    pub enable_logs:   bool,
+    pub configuration:  Configuration,
+    pub enable_logs:    bool,
+    pub launch_history: LaunchHistory,
+
+    pub catalog_overrides: CatalogOverrides,
+
+    pub keep_awake_sessions: KeepAwakeSessions,
+
+    // The `sysui_demo_allowed` setting devices in demo mode had before, `None` when it was not set.
+    pub demo_mode_allowances: BTreeMap<String, Option<String>>,
 }
 
 impl Umdb {
     pub fn new() -> Umdb {
-        Umdb { configuration: Configuration::new(), enable_logs: true }
+        Umdb {
+            configuration:       Configuration::new(),
+            enable_logs:         true,
+            launch_history:      LaunchHistory::default(),
+            catalog_overrides:   CatalogOverrides::default(),
+            keep_awake_sessions: KeepAwakeSessions::default(),
+
+            demo_mode_allowances: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for Umdb {
+    fn default() -> Self {
+        Self::new()
     }
 }
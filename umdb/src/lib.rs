@@ -1,7 +1,9 @@
 mod adb;
+mod apk;
 mod core;
 mod common;
 
+pub mod cli;
 pub mod rest;
 
-pub use core::Umdb;
+pub use core::{Umdb, UnlockCredential};
@@ -1,17 +1,193 @@
-use std::net::IpAddr;
+use std::{collections::BTreeMap, fs::File, io::{BufReader, Seek}, time::{Duration, Instant, SystemTime}};
 
-use actix_web::{error::ErrorBadRequest, HttpRequest, Responder, Result, web};
+use actix_web::{error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorPayloadTooLarge, ErrorRequestTimeout}, HttpRequest, HttpResponse, Responder, Result, web};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
 
-use crate::{adb::{executable::check_adb, device::adb_devices, links::adb_open_deep_link, connect::adb_connect}, core::System};
-use super::{ActixUmdbHandle, error_handling::{format_error, make_system_unsupported_reponse, MissingHeaderError, MalformedHeaderError}, headers::read_system_header, read_handle};
+use crate::{apk::manifest::{read_manifest_links, ApkManifestError}, adb::{pairing::{adb_pair, adb_pair_with_qr_code, adb_pairing_qr_payload}, mdns::adb_mdns_services, executable::check_adb, device::adb_devices, input::adb_send_input, ui::{adb_dump_ui, adb_ui_action}, flow::adb_run_flow_on_devices, activity::adb_activity_state, wait::{adb_wait, WaitError, WaitRequest}, power::{adb_dismiss_keyguard, adb_keep_awake, adb_restore_stay_on, adb_unlock, adb_wake, PowerError}, screenshot::{adb_demo_mode_screenshot, adb_screenshot}, permissions::{adb_change_permission, adb_package_permissions, adb_reset_permissions, PermissionChange}, demo_mode::{adb_enter_demo_mode, adb_exit_demo_mode, adb_restore_demo_allowed, DemoModeOptions, DemoModeRequest}, overlays::{adb_get_overlays, adb_set_overlay, Overlay}, baseline::{adb_capture_baseline, adb_diff_baseline, adb_restore_baseline}, settings::{adb_get_setting, adb_get_settings, adb_set_setting, SettingName, SettingValue}, links::adb_open_deep_link, connect::{adb_back_to_usb, adb_connect, adb_connect_from_usb, adb_disconnect}, fan_out::adb_resolve_targets}, common::{device::{DeviceListingOptions, DeviceProfile}, input::InputEvent, ui::{UiActionRequest, UiDumpOptions}, flow::{parse_flow, render_junit_report}, fan_out::{fan_out, DeviceTargets}, links::{DeepLinkOptions, OpenDeepLinkError, OpenDeepLinkResult}, qr::{render_qr_code, QrFormat}}, core::{System, Configuration, OperationPolicies, DEFAULT_KEEP_AWAKE_LEASE, Baselines, BaselinesError, CatalogLink, DeviceProfiles, DeviceProfilesError, IdentifiedCatalogLink, LaunchRecord, LinkCatalog, LinkCatalogError, expand_link_template, validate_link_template}};
+use super::{ActixUmdbHandle, error_handling::{format_error, make_system_unsupported_reponse, MissingHeaderError, MalformedBodyError, MalformedHeaderError}, headers::read_system_header, read_handle, read_configuration, write_handle};
+
+// Google Play rejects APKs larger than 200 MB.
+const MAXIMUM_APK_SIZE: usize = 200 * 1024 * 1024;
+
+const DEFAULT_QR_CODE_SCALE: u32 = 8;
+
+#[derive(Deserialize)]
+struct MultiDeviceLinkRequest {
+    link: String,
+    targets: DeviceTargets,
+    concurrency: Option<usize>,
+
+    // Restrict which app handles the link and pass extras, as catalog links do.
+    #[serde(default)] package: Option<String>,
+    #[serde(default)] extras:  BTreeMap<String, String>,
+}
+
+// Pairing either uses the code shown by the device, or the password of a QR code the device scanned.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PairingRequest {
+    Code { host: String, port: u16, code: String },
+    QrCode { service_name: String, password: String },
+}
+
+#[derive(Deserialize)]
+struct FlowRunRequest {
+    // YAML, as written in flow files.
+    flow: String,
+    targets: DeviceTargets,
+    concurrency: Option<usize>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FlowReportFormat {
+    #[default] Json,
+    Junit,
+}
+
+#[derive(Deserialize)]
+struct FlowRunQuery {
+    #[serde(default)] format: FlowReportFormat,
+}
+
+#[derive(Deserialize)]
+struct KeepAwakeQuery {
+    lease_ms: Option<u64>,
+}
+
+// Query strings cannot be flattened into `DemoModeOptions`, as they only hold strings.
+#[derive(Deserialize)]
+struct ScreenshotQuery {
+    #[serde(default)] demo_mode:          bool,
+    #[serde(default)] charging:           bool,
+    #[serde(default)] show_notifications: bool,
+
+    clock:         Option<String>,
+    battery_level: Option<u8>,
+    signal_level:  Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct DeviceListQuery {
+    #[serde(default)] include_link_local: bool,
+    #[serde(default)] query_devices:      bool,
+
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LinkListQuery {
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LaunchHistoryQuery {
+    device: Option<String>,
+    limit:  Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct LinkVariables {
+    #[serde(default)] variables: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct QrCodeQuery {
+    #[serde(default)] format: QrFormat,
+
+    scale: Option<u32>,
+}
+
+// Query structs are not flattened into each other: the query string deserializer cannot parse numbers in flattened fields.
+#[derive(Deserialize)]
+struct DataQrCodeQuery {
+    data: String,
+
+    #[serde(default)] format: QrFormat,
+
+    scale: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PairingQrCodeQuery {
+    name:     String,
+    password: String,
+
+    #[serde(default)] format: QrFormat,
+
+    scale: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct ExpandedLink {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct CatalogLinkLaunch {
+    url:    String,
+    result: OpenDeepLinkResult,
+}
 
 pub fn configure(config: &mut web::ServiceConfig, umdb: ActixUmdbHandle) {
     config
     .route("/devices", web::get().to(list_devices))
     .route("/configuration", web::get().to(get_config))
+    .route("/configuration/timeouts", web::put().to(set_timeouts))
     .route("/device/{id}/link", web::post().to(open_deep_link))
+    .route("/devices/link", web::post().to(open_deep_link_on_devices))
+    .route("/device/{id}/links/{link_id}", web::post().to(launch_catalog_link))
+    .route("/links", web::get().to(list_catalog_links))
+    .route("/links", web::post().to(create_catalog_link))
+    .route("/links/history", web::get().to(get_launch_history))
+    .route("/links/{link_id}", web::get().to(get_catalog_link))
+    .route("/links/{link_id}", web::put().to(replace_catalog_link))
+    .route("/links/{link_id}", web::delete().to(delete_catalog_link))
+    .route("/links/{link_id}/expansion", web::post().to(expand_catalog_link))
+    .route("/links/{link_id}/qr", web::get().to(get_catalog_link_qr_code))
+    .route("/qr", web::get().to(get_qr_code))
+    .route("/qr/pairing", web::get().to(get_pairing_qr_code))
     .route("/executable/check", web::get().to(check_executable))
+    .route("/apk/links", web::post().to(import_apk_links))
     .route("/device/{id}/connection", web::post().to(connect_tcpip))
+    .route("/device/{id}/connection", web::delete().to(disconnect_device))
+    .route("/device/{id}/usb", web::post().to(switch_back_to_usb))
+    .route("/device/{id}/input", web::post().to(send_input))
+    .route("/device/{id}/ui", web::get().to(dump_ui))
+    .route("/device/{id}/ui/action", web::post().to(run_ui_action))
+    .route("/device/{id}/activity", web::get().to(get_activity_state))
+    .route("/device/{id}/wake", web::post().to(wake_device))
+    .route("/device/{id}/keyguard/dismiss", web::post().to(dismiss_device_keyguard))
+    .route("/device/{id}/unlock", web::post().to(unlock_device))
+    .route("/device/{id}/keep-awake", web::post().to(start_keep_awake_session))
+    .route("/device/{id}/keep-awake", web::delete().to(end_keep_awake_session))
+    .route("/device/{id}/wait", web::post().to(wait_for_device))
+    .route("/device/{id}/screenshot", web::get().to(take_screenshot))
+    .route("/device/{id}/settings", web::get().to(get_device_settings))
+    .route("/device/{id}/settings/{name}", web::get().to(get_device_setting))
+    .route("/device/{id}/settings/{name}", web::put().to(set_device_setting))
+    .route("/device/{id}/package/{name}/permissions", web::get().to(get_package_permissions))
+    .route("/device/{id}/package/{name}/permissions", web::post().to(change_package_permission))
+    .route("/device/{id}/permissions/reset", web::post().to(reset_device_permissions))
+    .route("/device/{id}/demo-mode", web::post().to(set_device_demo_mode))
+    .route("/device/{id}/overlays", web::get().to(get_device_overlays))
+    .route("/device/{id}/overlays/{overlay}", web::put().to(set_device_overlay))
+    .route("/device/{id}/baselines/{name}", web::post().to(capture_device_baseline))
+    .route("/device/{id}/baselines/{name}/diff", web::get().to(diff_device_baseline))
+    .route("/device/{id}/baselines/{name}/restore", web::post().to(restore_device_baseline))
+    .route("/flows/run", web::post().to(run_flow))
+    .route("/connection", web::post().to(connect_remote_device))
+    .route("/profiles", web::get().to(list_device_profiles))
+    .route("/profiles/{serial}", web::get().to(get_device_profile))
+    .route("/profiles/{serial}", web::put().to(replace_device_profile))
+    .route("/profiles/{serial}", web::delete().to(delete_device_profile))
+    .route("/baselines", web::get().to(list_baselines))
+    .route("/baselines/{name}", web::get().to(get_baseline))
+    .route("/baselines/{name}", web::delete().to(delete_baseline))
+    .route("/pairing", web::post().to(pair_device))
+    .route("/mdns/services", web::get().to(list_mdns_services))
     .app_data(umdb);
 }
 
@@ -21,6 +197,14 @@ async fn get_config(actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
     Ok(web::Json(handle_guard.umdb.configuration.clone()))
 }
 
+async fn set_timeouts(actix_handle: ActixUmdbHandle, body: web::Json<OperationPolicies>) -> Result<impl Responder> {
+    let mut handle_guard = write_handle(&actix_handle)?;
+
+    handle_guard.umdb.configuration.timeouts = body.into_inner();
+
+    Ok(web::Json(handle_guard.umdb.configuration.timeouts.clone()))
+}
+
 // This route is dangerous! This allows the called to run any program on the server.
 async fn check_executable(request: HttpRequest) -> Result<impl Responder> {
     let path_header_name = "path";
@@ -47,7 +231,36 @@ async fn check_executable(request: HttpRequest) -> Result<impl Responder> {
     Ok("")
 }
 
-async fn list_devices(request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+// This route is dangerous! This allows the caller to read any file on the server.
+async fn import_apk_links(request: HttpRequest, mut body: web::Payload) -> Result<impl Responder> {
+    let path_header_name = "path";
+
+    // The APK is either uploaded as the request body, or read from the server at the path given in the header.
+    let apk = match receive_upload(&mut body, MAXIMUM_APK_SIZE).await? {
+        Some(file) => file,
+
+        None => {
+            let path = request
+            .headers()
+            .get(path_header_name)
+            .ok_or(ErrorBadRequest(format_error(MissingHeaderError(path_header_name))))?
+            .to_str()
+            .map_err(|_| ErrorBadRequest(format_error(MalformedHeaderError(path_header_name))))?;
+
+            File::open(path).map_err(|error| ErrorBadRequest(format_error(ApkManifestError::CannotReadFile(error.to_string()))))?
+        },
+    };
+
+    // Unzipping and decoding the manifest would hold up the other requests of the worker.
+    let links = web
+    ::block(move || read_manifest_links(BufReader::new(apk)))
+    .await?
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(links))
+}
+
+async fn list_devices(request: HttpRequest, query: web::Query<DeviceListQuery>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
     let system = read_system_header(&request).map_err(|error| {
         ErrorBadRequest(format_error(error))
     })?;
@@ -56,12 +269,30 @@ async fn list_devices(request: HttpRequest, actix_handle: ActixUmdbHandle) -> Re
         return Err(make_system_unsupported_reponse());
     }
 
-    let handle_guard = read_handle(&actix_handle)?;
+    let (configuration, catalog_overrides) = {
+        let mut handle_guard = write_handle(&actix_handle)?;
 
-    let devices = adb_devices(&handle_guard.umdb.configuration)
+        let umdb = &mut handle_guard.umdb;
+
+        let catalog_overrides = umdb.catalog_overrides.get(&umdb.configuration).unwrap_or_else(|error| {
+            log::warn!("Marketing names are only looked up in the bundled catalog, as the override file cannot be read: {error:?}");
+
+            None
+        });
+
+        (umdb.configuration.clone(), catalog_overrides)
+    };
+
+    let options = DeviceListingOptions { include_link_local: query.include_link_local, query_devices: query.query_devices };
+
+    let mut devices = adb_devices(&configuration, &options, catalog_overrides.as_deref())
     .await
     .map_err(|error| ErrorBadRequest(format_error(error)))?;
 
+    if let Some(label) = &query.label {
+        devices.retain(|device| device.has_label(label));
+    }
+
     Ok(web::Json(devices))
 }
 
@@ -81,19 +312,193 @@ async fn open_deep_link(path: web::Path<String>, request: HttpRequest, actix_han
         return Err(make_system_unsupported_reponse());
     }
 
-    let handle_guard = read_handle(&actix_handle)?;
+    let configuration = read_configuration(&actix_handle)?;
 
-    let devices = adb_open_deep_link(&handle_guard.umdb.configuration, &device_id, &link).map_err(|error| {
+    let (result, record) = timed_launch(&configuration, &device_id, &link, None, &DeepLinkOptions::default()).await;
+
+    record_launch(&actix_handle, record)?;
+
+    let result = result.map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(result))
+}
+
+// This route is dangerous! This allows the called to run any program on the server.
+async fn open_deep_link_on_devices(request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<MultiDeviceLinkRequest>) -> Result<impl Responder> {
+    let system = read_system_header(&request).map_err(|error| {
         ErrorBadRequest(format_error(error))
     })?;
 
-    Ok(web::Json(devices))
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let device_ids = adb_resolve_targets(&configuration, &body.targets)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let concurrency = body.concurrency.unwrap_or(configuration.fan_out_concurrency);
+    let options     = DeepLinkOptions { package: body.package.as_deref(), extras: Some(&body.extras) };
+
+    let launches = fan_out(device_ids, concurrency, |device_id| {
+        let configuration = &configuration;
+        let link          = &body.link;
+        let options       = &options;
+
+        async move { timed_launch(configuration, &device_id, link, None, options).await }
+    })
+    .await;
+
+    let mut results = BTreeMap::new();
+
+    for (device_id, (result, record)) in launches {
+        record_launch(&actix_handle, record)?;
+
+        results.insert(device_id, result);
+    }
+
+    Ok(web::Json(results))
+}
+
+// This route is dangerous! This allows the called to run any program on the server.
+async fn launch_catalog_link(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Bytes) -> Result<impl Responder> {
+    let (device_id, link_id) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let link = LinkCatalog
+    ::load(&configuration)
+    .and_then(|catalog| catalog.get(&link_id).cloned())
+    .map_err(make_link_catalog_error_response)?;
+
+    let variables = read_link_variables(&body)?;
+
+    let url = expand_link_template(&link, &variables).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let options = DeepLinkOptions { package: link.package.as_deref(), extras: Some(&link.extras) };
+
+    let (result, record) = timed_launch(&configuration, &device_id, &url, Some(&link_id), &options).await;
+
+    record_launch(&actix_handle, record)?;
+
+    let result = result.map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(CatalogLinkLaunch { url, result }))
+}
+
+async fn expand_catalog_link(path: web::Path<String>, actix_handle: ActixUmdbHandle, body: web::Bytes) -> Result<impl Responder> {
+    let link_id = path.into_inner();
+
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let link = LinkCatalog
+    ::load(&handle_guard.umdb.configuration)
+    .and_then(|catalog| catalog.get(&link_id).cloned())
+    .map_err(make_link_catalog_error_response)?;
+
+    let variables = read_link_variables(&body)?;
+
+    let url = expand_link_template(&link, &variables).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(ExpandedLink { url }))
+}
+
+async fn list_catalog_links(query: web::Query<LinkListQuery>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let catalog = LinkCatalog::load(&handle_guard.umdb.configuration).map_err(make_link_catalog_error_response)?;
+
+    Ok(web::Json(catalog.list(query.tag.as_deref())))
+}
+
+async fn get_catalog_link(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let link_id = path.into_inner();
+
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let link = LinkCatalog
+    ::load(&handle_guard.umdb.configuration)
+    .and_then(|catalog| catalog.get(&link_id).cloned())
+    .map_err(make_link_catalog_error_response)?;
+
+    Ok(web::Json(IdentifiedCatalogLink { id: link_id, link }))
+}
+
+async fn create_catalog_link(actix_handle: ActixUmdbHandle, body: web::Json<CatalogLink>) -> Result<impl Responder> {
+    // The write lock serializes catalog updates made through this server.
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let link = body.into_inner();
+
+    validate_link_template(&link).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let mut catalog = LinkCatalog::load(configuration).map_err(make_link_catalog_error_response)?;
+
+    let id = catalog.insert(link.clone()).map_err(make_link_catalog_error_response)?;
+
+    catalog.save(configuration).map_err(make_link_catalog_error_response)?;
+
+    Ok(HttpResponse::Created().json(IdentifiedCatalogLink { id, link }))
+}
+
+async fn replace_catalog_link(path: web::Path<String>, actix_handle: ActixUmdbHandle, body: web::Json<CatalogLink>) -> Result<impl Responder> {
+    let link_id = path.into_inner();
+
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let link = body.into_inner();
+
+    validate_link_template(&link).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let mut catalog = LinkCatalog::load(configuration).map_err(make_link_catalog_error_response)?;
+
+    catalog.replace(&link_id, link.clone());
+
+    catalog.save(configuration).map_err(make_link_catalog_error_response)?;
+
+    Ok(web::Json(IdentifiedCatalogLink { id: link_id, link }))
+}
+
+async fn delete_catalog_link(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let link_id = path.into_inner();
+
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let mut catalog = LinkCatalog::load(configuration).map_err(make_link_catalog_error_response)?;
+
+    catalog.remove(&link_id).map_err(make_link_catalog_error_response)?;
+
+    catalog.save(configuration).map_err(make_link_catalog_error_response)?;
+
+    Ok("")
+}
+
+async fn get_launch_history(query: web::Query<LaunchHistoryQuery>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let mut handle_guard = write_handle(&actix_handle)?;
+
+    let umdb = &mut handle_guard.umdb;
+
+    Ok(web::Json(umdb.launch_history.list(&umdb.configuration, query.device.as_deref(), query.limit)))
 }
 
 async fn connect_tcpip(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
     let device_id = path.into_inner();
-    let port_header_name = "port";
-    let ip_header_name = "ip";
 
     let system = read_system_header(&request).map_err(|error| {
         ErrorBadRequest(format_error(error))
@@ -103,29 +508,979 @@ async fn connect_tcpip(path: web::Path<String>, request: HttpRequest, actix_hand
         return Err(make_system_unsupported_reponse());
     }
 
-    let handle_guard = read_handle(&actix_handle)?;
+    let configuration = read_configuration(&actix_handle)?;
 
-    let ip = request
-    .headers()
-    .get(ip_header_name)
-    .ok_or(ErrorBadRequest(format_error(MissingHeaderError(ip_header_name))))?
-    .to_str()
-    .map(|ip| ip.parse::<IpAddr>())
-    .unwrap()
-    .map_err(|_| ErrorBadRequest(format_error(MalformedHeaderError(ip_header_name))))?;
+    let (host, port) = read_connection_headers(&request)?;
 
-    let port = request
-    .headers()
-    .get(port_header_name)
-    .ok_or(ErrorBadRequest(format_error(MissingHeaderError(port_header_name))))?
-    .to_str()
-    .map(|port| port.parse::<u16>())
-    .unwrap()
-    .map_err(|_| ErrorBadRequest(format_error(MalformedHeaderError(port_header_name))))?;
+    let connection = adb_connect_from_usb(&configuration, &device_id, &host, port)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(connection))
+}
+
+async fn connect_remote_device(request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let (host, port) = read_connection_headers(&request)?;
+
+    let connection = adb_connect(&configuration, &host, port)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(connection))
+}
+
+async fn disconnect_device(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
 
-    adb_connect(&handle_guard.umdb.configuration, &device_id, &ip, port)
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_disconnect(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+async fn switch_back_to_usb(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_back_to_usb(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+async fn send_input(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<InputEvent>) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_send_input(&configuration, &device_id, &body)
     .await
     .map_err(|error| ErrorBadRequest(format_error(error)))?;
 
     Ok("")
 }
+
+async fn dump_ui(path: web::Path<String>, request: HttpRequest, query: web::Query<UiDumpOptions>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let hierarchy = adb_dump_ui(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(match query.compact {
+        true  => hierarchy.compact(),
+        false => hierarchy,
+    }))
+}
+
+async fn run_ui_action(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<UiActionRequest>) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let node = adb_ui_action(&configuration, &device_id, &body)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(node))
+}
+
+async fn get_activity_state(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let state = adb_activity_state(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(state))
+}
+
+async fn wake_device(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_wake(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+async fn dismiss_device_keyguard(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_dismiss_keyguard(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+// Uses the credential of the device from the configuration.
+async fn unlock_device(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_unlock(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+// Keeps the screen on until the session ends or its lease expires. Clients renew the lease by starting the session again.
+async fn start_keep_awake_session(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle, query: web::Query<KeepAwakeQuery>) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let previous_value = adb_keep_awake(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let lease = query.lease_ms.map(Duration::from_millis).unwrap_or(DEFAULT_KEEP_AWAKE_LEASE);
+
+    write_handle(&actix_handle)?
+    .umdb
+    .keep_awake_sessions
+    .start(&device_id, previous_value, lease);
+
+    actix_web::rt::spawn(end_expired_keep_awake_session(actix_handle.clone(), device_id, lease));
+
+    Ok("")
+}
+
+async fn end_expired_keep_awake_session(actix_handle: ActixUmdbHandle, device_id: String, lease: Duration) {
+    tokio::time::sleep(lease).await;
+
+    let (configuration, previous_value) = match write_handle(&actix_handle) {
+        Ok(mut handle_guard) => (handle_guard.umdb.configuration.clone(), handle_guard.umdb.keep_awake_sessions.end_if_expired(&device_id)),
+        Err(_)               => return,
+    };
+
+    let Some(previous_value) = previous_value else {
+        return;
+    };
+
+    if let Err(error) = adb_restore_stay_on(&configuration, &device_id, previous_value).await {
+        log::warn!("Could not restore the screen timeout of {device_id} once its keep-awake session expired: {}", format_error(error));
+    }
+}
+
+// Servers call this once stopped, so that devices do not stay awake after them.
+pub async fn end_keep_awake_sessions(actix_handle: &ActixUmdbHandle) {
+    let (configuration, sessions) = match write_handle(actix_handle) {
+        Ok(mut handle_guard) => (handle_guard.umdb.configuration.clone(), handle_guard.umdb.keep_awake_sessions.end_all()),
+        Err(_)               => return,
+    };
+
+    let restorations = sessions
+    .iter()
+    .map(|(device_id, previous_value)| adb_restore_stay_on(&configuration, device_id, *previous_value));
+
+    for ((device_id, _), result) in sessions.iter().zip(futures::future::join_all(restorations).await) {
+        if let Err(error) = result {
+            log::warn!("Could not restore the screen timeout of {device_id}: {}", format_error(error));
+        }
+    }
+}
+
+// Restores the setting the device had when the session started.
+async fn end_keep_awake_session(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let previous_value = write_handle(&actix_handle)?
+    .umdb
+    .keep_awake_sessions
+    .end(&device_id)
+    .ok_or_else(|| ErrorNotFound(format_error(PowerError::NoKeepAwakeSession)))?;
+
+    // The session is kept when the device cannot be reached, so that ending it can be retried.
+    if let Err(error) = adb_restore_stay_on(&configuration, &device_id, previous_value).await {
+        write_handle(&actix_handle)?
+        .umdb
+        .keep_awake_sessions
+        .start(&device_id, previous_value, DEFAULT_KEEP_AWAKE_LEASE);
+
+        return Err(ErrorBadRequest(format_error(error)));
+    }
+
+    Ok("")
+}
+
+// Blocks until one of the conditions holds, or the timeout expires.
+async fn wait_for_device(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<WaitRequest>) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let outcome = adb_wait(&configuration, &device_id, &body)
+    .await
+    .map_err(make_wait_error_response)?;
+
+    Ok(web::Json(outcome))
+}
+
+// With `demo_mode`, the status bar shows the demo mode icons described by the other parameters.
+async fn take_screenshot(path: web::Path<String>, request: HttpRequest, query: web::Query<ScreenshotQuery>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let query = query.into_inner();
+
+    let demo_mode_options = DemoModeOptions {
+        clock:              query.clock,
+        battery_level:      query.battery_level,
+        charging:           query.charging,
+        signal_level:       query.signal_level,
+        show_notifications: query.show_notifications,
+    };
+
+    let image = match query.demo_mode {
+        true  => adb_demo_mode_screenshot(&configuration, &device_id, &demo_mode_options).await.map_err(|error| ErrorBadRequest(format_error(error)))?,
+        false => adb_screenshot(&configuration, &device_id).await.map_err(|error| ErrorBadRequest(format_error(error)))?,
+    };
+
+    Ok(HttpResponse::Ok().content_type("image/png").body(image))
+}
+
+async fn get_device_settings(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    Ok(web::Json(adb_get_settings(&configuration, &device_id).await))
+}
+
+async fn get_device_setting(path: web::Path<(String, SettingName)>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let (device_id, name) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let value = adb_get_setting(&configuration, &device_id, name)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(value))
+}
+
+// The body is the new value of the setting, and the response its previous value, so that changes can be undone.
+async fn set_device_setting(path: web::Path<(String, SettingName)>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<serde_json::Value>) -> Result<impl Responder> {
+    let (device_id, name) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let value = serde_json
+    ::from_value::<SettingValue>(serde_json::json!({ "setting": name, "value": body.into_inner() }))
+    .map_err(|error| ErrorBadRequest(format_error(MalformedBodyError(error.to_string()))))?;
+
+    let previous_value = adb_set_setting(&configuration, &device_id, &value)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(previous_value))
+}
+
+async fn get_package_permissions(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let (device_id, package) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let permissions = adb_package_permissions(&configuration, &device_id, &package)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(permissions))
+}
+
+async fn change_package_permission(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<PermissionChange>) -> Result<impl Responder> {
+    let (device_id, package) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let permissions = adb_change_permission(&configuration, &device_id, &package, &body)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(permissions))
+}
+
+// Resets the runtime permissions of all apps of the device.
+async fn reset_device_permissions(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    adb_reset_permissions(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok("")
+}
+
+async fn set_device_demo_mode(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<DemoModeRequest>) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    if body.enabled {
+        let previous_allowed = adb_enter_demo_mode(&configuration, &device_id, &body.options)
+        .await
+        .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+        // Entering demo mode again keeps the setting the device had at first.
+        write_handle(&actix_handle)?
+        .umdb
+        .demo_mode_allowances
+        .entry(device_id)
+        .or_insert(previous_allowed);
+
+        return Ok("");
+    }
+
+    adb_exit_demo_mode(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    // Left as is when demo mode was not entered through this server.
+    let previous_allowed = write_handle(&actix_handle)?
+    .umdb
+    .demo_mode_allowances
+    .remove(&device_id);
+
+    if let Some(previous_allowed) = previous_allowed {
+        adb_restore_demo_allowed(&configuration, &device_id, previous_allowed.as_deref())
+        .await
+        .map_err(|error| ErrorBadRequest(format_error(error)))?;
+    }
+
+    Ok("")
+}
+
+async fn get_device_overlays(path: web::Path<String>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let device_id = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let overlays = adb_get_overlays(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(overlays))
+}
+
+// The body is whether the overlay should be shown.
+async fn set_device_overlay(path: web::Path<(String, Overlay)>, request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<bool>) -> Result<impl Responder> {
+    let (device_id, overlay) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let overlays = adb_set_overlay(&configuration, &device_id, overlay, body.into_inner())
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(overlays))
+}
+
+// Replaces the baseline of the same name, if any.
+async fn capture_device_baseline(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let (device_id, name) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let baseline = adb_capture_baseline(&configuration, &device_id)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    // Only taken once the device was read, so that other requests are not blocked meanwhile.
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let mut baselines = Baselines::load(configuration).map_err(make_baselines_error_response)?;
+
+    baselines.baselines.insert(name, baseline.clone());
+
+    baselines.save(configuration).map_err(make_baselines_error_response)?;
+
+    Ok(web::Json(baseline))
+}
+
+async fn diff_device_baseline(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let (device_id, name) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let baseline = Baselines
+    ::load(&configuration)
+    .and_then(|baselines| baselines.get(&name).cloned())
+    .map_err(make_baselines_error_response)?;
+
+    let differences = adb_diff_baseline(&configuration, &device_id, &baseline)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(differences))
+}
+
+async fn restore_device_baseline(path: web::Path<(String, String)>, request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let (device_id, name) = path.into_inner();
+
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let baseline = Baselines
+    ::load(&configuration)
+    .and_then(|baselines| baselines.get(&name).cloned())
+    .map_err(make_baselines_error_response)?;
+
+    let restoration = adb_restore_baseline(&configuration, &device_id, &baseline)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(restoration))
+}
+
+// This route is dangerous! Flows open arbitrary links and type arbitrary text on the devices.
+async fn run_flow(request: HttpRequest, query: web::Query<FlowRunQuery>, actix_handle: ActixUmdbHandle, body: web::Json<FlowRunRequest>) -> Result<impl Responder> {
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let flow = parse_flow(&body.flow).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    let results = adb_run_flow_on_devices(&configuration, &flow, &body.targets, body.concurrency)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(match query.format {
+        FlowReportFormat::Json  => HttpResponse::Ok().json(results),
+        FlowReportFormat::Junit => HttpResponse::Ok().content_type("application/xml").body(render_junit_report(&results)),
+    })
+}
+
+async fn list_device_profiles(actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let profiles = DeviceProfiles::load(&handle_guard.umdb.configuration).map_err(make_device_profiles_error_response)?;
+
+    Ok(web::Json(profiles.profiles))
+}
+
+async fn get_device_profile(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let serial = path.into_inner();
+
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let profile = DeviceProfiles
+    ::load(&handle_guard.umdb.configuration)
+    .and_then(|profiles| profiles.get(&serial).cloned())
+    .map_err(make_device_profiles_error_response)?;
+
+    Ok(web::Json(profile))
+}
+
+async fn replace_device_profile(path: web::Path<String>, actix_handle: ActixUmdbHandle, body: web::Json<DeviceProfile>) -> Result<impl Responder> {
+    let serial = path.into_inner();
+
+    // The write lock serializes profile updates made through this server.
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let profile = body.into_inner();
+
+    let mut profiles = DeviceProfiles::load(configuration).map_err(make_device_profiles_error_response)?;
+
+    profiles.profiles.insert(serial, profile.clone());
+
+    profiles.save(configuration).map_err(make_device_profiles_error_response)?;
+
+    Ok(web::Json(profile))
+}
+
+async fn delete_device_profile(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let serial = path.into_inner();
+
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let mut profiles = DeviceProfiles::load(configuration).map_err(make_device_profiles_error_response)?;
+
+    profiles.remove(&serial).map_err(make_device_profiles_error_response)?;
+
+    profiles.save(configuration).map_err(make_device_profiles_error_response)?;
+
+    Ok("")
+}
+
+async fn list_baselines(actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let baselines = Baselines::load(&handle_guard.umdb.configuration).map_err(make_baselines_error_response)?;
+
+    Ok(web::Json(baselines.baselines))
+}
+
+async fn get_baseline(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let name = path.into_inner();
+
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let baseline = Baselines
+    ::load(&handle_guard.umdb.configuration)
+    .and_then(|baselines| baselines.get(&name).cloned())
+    .map_err(make_baselines_error_response)?;
+
+    Ok(web::Json(baseline))
+}
+
+async fn delete_baseline(path: web::Path<String>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let name = path.into_inner();
+
+    let handle_guard = write_handle(&actix_handle)?;
+
+    let configuration = &handle_guard.umdb.configuration;
+
+    let mut baselines = Baselines::load(configuration).map_err(make_baselines_error_response)?;
+
+    baselines.remove(&name).map_err(make_baselines_error_response)?;
+
+    baselines.save(configuration).map_err(make_baselines_error_response)?;
+
+    Ok("")
+}
+
+async fn pair_device(request: HttpRequest, actix_handle: ActixUmdbHandle, body: web::Json<PairingRequest>) -> Result<impl Responder> {
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let result = match body.into_inner() {
+        PairingRequest::Code { host, port, code }         => adb_pair(&configuration, &host, port, &code).await,
+        PairingRequest::QrCode { service_name, password } => adb_pair_with_qr_code(&configuration, &service_name, &password).await,
+    };
+
+    let paired_device = result.map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(paired_device))
+}
+
+async fn list_mdns_services(request: HttpRequest, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let system = read_system_header(&request).map_err(|error| {
+        ErrorBadRequest(format_error(error))
+    })?;
+
+    if system != System::Android {
+        return Err(make_system_unsupported_reponse());
+    }
+
+    let configuration = read_configuration(&actix_handle)?;
+
+    let services = adb_mdns_services(&configuration)
+    .await
+    .map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(web::Json(services))
+}
+
+async fn get_qr_code(query: web::Query<DataQrCodeQuery>) -> Result<impl Responder> {
+    make_qr_code_response(&query.data, query.format, query.scale)
+}
+
+async fn get_catalog_link_qr_code(path: web::Path<String>, query: web::Query<QrCodeQuery>, actix_handle: ActixUmdbHandle) -> Result<impl Responder> {
+    let link_id = path.into_inner();
+
+    let handle_guard = read_handle(&actix_handle)?;
+
+    let link = LinkCatalog
+    ::load(&handle_guard.umdb.configuration)
+    .and_then(|catalog| catalog.get(&link_id).cloned())
+    .map_err(make_link_catalog_error_response)?;
+
+    // Templates are expanded with their defaults, since there is no way to pass variables to a scanned code.
+    let url = expand_link_template(&link, &BTreeMap::new()).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    make_qr_code_response(&url, query.format, query.scale)
+}
+
+async fn get_pairing_qr_code(query: web::Query<PairingQrCodeQuery>) -> Result<impl Responder> {
+    make_qr_code_response(&adb_pairing_qr_payload(&query.name, &query.password), query.format, query.scale)
+}
+
+fn make_qr_code_response(data: &str, format: QrFormat, scale: Option<u32>) -> Result<HttpResponse> {
+    let image = render_qr_code(data, format, scale.unwrap_or(DEFAULT_QR_CODE_SCALE)).map_err(|error| ErrorBadRequest(format_error(error)))?;
+
+    Ok(HttpResponse::Ok().content_type(format.content_type()).body(image))
+}
+
+// The device is reached through the `hostname` header, which also accepts IPs. The `ip` header is kept for older clients.
+fn read_connection_headers(request: &HttpRequest) -> Result<(String, u16)> {
+    let port_header_name = "port";
+
+    let (host_header_name, host) = ["hostname", "ip"]
+    .iter()
+    .find_map(|name| request.headers().get(*name).map(|value| (*name, value)))
+    .ok_or(ErrorBadRequest(format_error(MissingHeaderError("hostname"))))?;
+
+    let host = host
+    .to_str()
+    .map_err(|_| ErrorBadRequest(format_error(MalformedHeaderError(host_header_name))))?
+    .trim_start_matches('[')
+    .trim_end_matches(']')
+    .to_string();
+
+    if host.is_empty() {
+        return Err(ErrorBadRequest(format_error(MalformedHeaderError(host_header_name))));
+    }
+
+    let port = request
+    .headers()
+    .get(port_header_name)
+    .ok_or(ErrorBadRequest(format_error(MissingHeaderError(port_header_name))))?
+    .to_str()
+    .ok()
+    .and_then(|port| port.parse::<u16>().ok())
+    .ok_or(ErrorBadRequest(format_error(MalformedHeaderError(port_header_name))))?;
+
+    Ok((host, port))
+}
+
+// Streams the body to an anonymous temporary file, removed once closed, rather than keeping it in memory. `None` when
+// the body is empty.
+async fn receive_upload(body: &mut web::Payload, maximum_size: usize) -> Result<Option<File>> {
+    let write_error = |error: std::io::Error| ErrorInternalServerError(format_error(ApkManifestError::CannotWriteFile(error.to_string())));
+
+    let mut file = None::<tokio::fs::File>;
+    let mut size = 0;
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+
+        size += chunk.len();
+
+        if size > maximum_size {
+            return Err(ErrorPayloadTooLarge(format_error(ApkManifestError::TooLarge(maximum_size))));
+        }
+
+        let file = match &mut file {
+            Some(file) => file,
+            None       => file.insert(tokio::fs::File::from_std(tempfile::tempfile().map_err(write_error)?)),
+        };
+
+        file.write_all(&chunk).await.map_err(write_error)?;
+    }
+
+    let Some(mut file) = file else {
+        return Ok(None);
+    };
+
+    file.flush().await.map_err(write_error)?;
+
+    let mut file = file.into_std().await;
+
+    file.rewind().map_err(write_error)?;
+
+    Ok(Some(file))
+}
+
+// An empty body means that no variable is given. A body that does not parse is rejected, rather than launching the
+// link without the substitutions the caller asked for.
+fn read_link_variables(body: &web::Bytes) -> Result<BTreeMap<String, serde_json::Value>> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(BTreeMap::new());
+    }
+
+    serde_json
+    ::from_slice::<LinkVariables>(body)
+    .map(|body| body.variables)
+    .map_err(|error| ErrorBadRequest(format_error(MalformedBodyError(error.to_string()))))
+}
+
+// Each launch is timed on its own, so that launches made on several devices at once get their own timestamp and duration.
+async fn timed_launch(configuration: &Configuration, device_id: &str, link: &str, link_id: Option<&str>, options: &DeepLinkOptions<'_>) -> (Result<OpenDeepLinkResult, OpenDeepLinkError>, LaunchRecord) {
+    let started_at = SystemTime::now();
+    let started    = Instant::now();
+
+    let result = adb_open_deep_link(configuration, device_id, link, options).await;
+
+    let record = LaunchRecord::new(device_id, link, link_id, started_at, started.elapsed(), &result);
+
+    (result, record)
+}
+
+fn record_launch(actix_handle: &ActixUmdbHandle, record: LaunchRecord) -> Result<()> {
+    let mut handle_guard = write_handle(actix_handle)?;
+
+    let umdb = &mut handle_guard.umdb;
+
+    umdb.launch_history.record(&umdb.configuration, record);
+
+    Ok(())
+}
+
+fn make_link_catalog_error_response(error: LinkCatalogError) -> actix_web::Error {
+    match error {
+        LinkCatalogError::LinkNotFound(_) => ErrorNotFound(format_error(error)),
+        _                                 => ErrorBadRequest(format_error(error)),
+    }
+}
+
+fn make_device_profiles_error_response(error: DeviceProfilesError) -> actix_web::Error {
+    match error {
+        DeviceProfilesError::ProfileNotFound(_) => ErrorNotFound(format_error(error)),
+        _                                       => ErrorBadRequest(format_error(error)),
+    }
+}
+
+fn make_baselines_error_response(error: BaselinesError) -> actix_web::Error {
+    match error {
+        BaselinesError::BaselineNotFound(_) => ErrorNotFound(format_error(error)),
+        _                                   => ErrorBadRequest(format_error(error)),
+    }
+}
+
+fn make_wait_error_response(error: WaitError) -> actix_web::Error {
+    match error {
+        WaitError::TimedOut => ErrorRequestTimeout(format_error(error)),
+        _                   => ErrorBadRequest(format_error(error)),
+    }
+}
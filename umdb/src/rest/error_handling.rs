@@ -19,6 +19,9 @@ pub struct MissingHeaderError<'a>(pub &'a str);
 #[derive(Serialize)]
 pub struct MalformedHeaderError<'a>(pub &'a str);
 
+#[derive(Serialize)]
+pub struct MalformedBodyError(pub String);
+
 pub fn make_system_unsupported_reponse() -> Error {
     ErrorBadRequest(format_error(SystemUnsupportedError {}))
 }
@@ -1,9 +1,9 @@
-use std::sync::{RwLock, RwLockReadGuard};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use tokio::sync::mpsc::WeakUnboundedSender;
 use actix_web::{web::Data, error::ErrorInternalServerError};
 
-use crate::Umdb;
+use crate::{Umdb, core::Configuration};
 
 #[derive(Debug)]
 pub enum FatalError {
@@ -49,3 +49,20 @@ pub fn read_handle<'a>(actix_handle: &'a ActixUmdbHandle) -> actix_web::Result<R
         ErrorInternalServerError("")
     })
 }
+
+pub fn write_handle<'a>(actix_handle: &'a ActixUmdbHandle) -> actix_web::Result<RwLockWriteGuard<'a, UmdbHandle>> {
+    actix_handle
+    .write()
+    .map_err(|error| {
+        let handle = &error.get_ref();
+
+        handle.signal_fatal(FatalError::CentralLockPoisoned);
+
+        ErrorInternalServerError("")
+    })
+}
+
+// Lock guards must not be held across await points, so async routes work on a snapshot of the configuration.
+pub fn read_configuration(actix_handle: &ActixUmdbHandle) -> actix_web::Result<Configuration> {
+    Ok(read_handle(actix_handle)?.umdb.configuration.clone())
+}